@@ -0,0 +1,380 @@
+use crate::core::*;
+use crate::models::*;
+use crate::prsr::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const FLE_PTH: &str = "adr_report.json";
+
+/// How addresses are chosen for a person.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdrSelect {
+    /// Every address.
+    #[default]
+    All,
+    /// Addresses in Washington, DC.
+    DcOnly,
+    /// The address nearest a reference ZIP Code.
+    ///
+    /// Nearness is by ZIP Code: the longest shared leading digits, then the
+    /// smallest difference. It is not a distance.
+    Nearest(u32),
+}
+
+impl fmt::Display for AdrSelect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AdrSelect::All => write!(f, "all"),
+            AdrSelect::DcOnly => write!(f, "DC only"),
+            AdrSelect::Nearest(zip5) => write!(f, "nearest {:05}", zip5),
+        }
+    }
+}
+
+/// An address selection policy for a source or role.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdrPolicy {
+    /// The source key, such as `house`.
+    ///
+    /// Empty matches every source.
+    #[serde(default)]
+    pub source: String,
+    /// The role. None matches every role.
+    #[serde(default)]
+    pub role: Option<Role>,
+    #[serde(default)]
+    pub select: AdrSelect,
+    /// The most addresses per person.
+    ///
+    /// Zero keeps every selected address, or one nearest address.
+    #[serde(default)]
+    pub max: usize,
+}
+
+impl AdrPolicy {
+    /// Whether the policy applies to a person.
+    pub fn is_match(&self, per: &Person) -> bool {
+        (self.source.is_empty() || self.source == per.src)
+            && self
+                .role
+                .as_ref()
+                .is_none_or(|o| per.role.as_ref() == Some(o))
+    }
+
+    /// Splits a person's addresses into chosen and skipped addresses.
+    ///
+    /// DC only keeps the first address when none are in DC.
+    pub fn select(&self, adrs: &[Address]) -> (Vec<Address>, Vec<Address>) {
+        let mut idxs: Vec<usize> = match self.select {
+            AdrSelect::All => (0..adrs.len()).collect(),
+            AdrSelect::DcOnly => {
+                let idxs: Vec<usize> = (0..adrs.len())
+                    .filter(|idx| adrs[*idx].state == "DC")
+                    .collect();
+                if idxs.is_empty() {
+                    (0..adrs.len().min(1)).collect()
+                } else {
+                    idxs
+                }
+            }
+            AdrSelect::Nearest(zip5) => {
+                let mut idxs: Vec<usize> = (0..adrs.len()).collect();
+                idxs.sort_by_key(|idx| zip_distance(adrs[*idx].zip5, zip5));
+                idxs
+            }
+        };
+        let max = match (self.max, self.select) {
+            (0, AdrSelect::Nearest(_)) => 1,
+            (0, _) => usize::MAX,
+            (max, _) => max,
+        };
+        idxs.truncate(max);
+        idxs.sort_unstable();
+        split_adrs(adrs, &idxs)
+    }
+}
+
+impl fmt::Display for AdrPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.select)?;
+        if self.max != 0 {
+            write!(f, " max {}", self.max)?;
+        }
+        Ok(())
+    }
+}
+
+/// An explicit address choice for one person.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdrOverride {
+    /// The source key, such as `house`.
+    ///
+    /// Empty matches every source.
+    #[serde(default)]
+    pub source: String,
+    /// The person's name, such as `Lou Correa`.
+    pub name: String,
+    /// The first address lines to keep, such as `2301 RAYBURN HOB`.
+    pub address1: Vec<String>,
+}
+
+impl AdrOverride {
+    /// Whether the override names a person.
+    ///
+    /// Names match on family and preferred given names.
+    pub fn is_match(&self, per: &Person) -> bool {
+        (self.source.is_empty() || self.source == per.src)
+            && name_parse(&self.name).key() == per.name_key()
+    }
+
+    /// Splits a person's addresses into the listed and other addresses.
+    pub fn select(&self, adrs: &[Address]) -> (Vec<Address>, Vec<Address>) {
+        let norm = |s: &str| dot_remove(s.to_uppercase());
+        let idxs: Vec<usize> = (0..adrs.len())
+            .filter(|idx| {
+                self.address1
+                    .iter()
+                    .any(|o| norm(o) == norm(&adrs[*idx].address1))
+            })
+            .collect();
+        split_adrs(adrs, &idxs)
+    }
+}
+
+/// The addresses chosen for one person.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdrChoice {
+    pub name: String,
+    pub org: String,
+    /// The policy used, such as `nearest 80302 max 2` or `override`.
+    pub policy: String,
+    pub chosen: Vec<Address>,
+    pub skipped: Vec<Address>,
+}
+
+/// Addresses chosen and skipped for each person before mailpieces are
+/// built.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdrReport {
+    pub choices: Vec<AdrChoice>,
+    /// People whose override matched none of their addresses.
+    ///
+    /// The policy chooses their addresses instead.
+    #[serde(default)]
+    pub unmatched: Vec<String>,
+}
+
+impl AdrReport {
+    /// Writes the report to disk.
+    pub fn save(&self) -> Result<()> {
+        write_to_file(self, FLE_PTH)
+    }
+}
+
+impl fmt::Display for AdrReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let chosen_cnt: usize = self.choices.iter().map(|o| o.chosen.len()).sum();
+        let skipped_cnt: usize = self.choices.iter().map(|o| o.skipped.len()).sum();
+        write!(f, "chose {} addresses, skipped {}", chosen_cnt, skipped_cnt)?;
+        for name in self.unmatched.iter() {
+            write!(f, "\n  override matched no address: {}", name)?;
+        }
+        // List people with skipped addresses.
+        for choice in self.choices.iter().filter(|o| !o.skipped.is_empty()) {
+            write!(f, "\n  {} ({}): {}", choice.name, choice.org, choice.policy)?;
+            for adr in choice.chosen.iter() {
+                write!(f, "\n    + {}", adr)?;
+            }
+            for adr in choice.skipped.iter() {
+                write!(f, "\n    - {}", adr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Chooses addresses for each person.
+///
+/// An override for the person comes first, then the first matching policy.
+/// An override that matches none of the person's addresses is reported
+/// and the policy applies. People without a match keep every address.
+pub fn select_adrs(
+    pers: &[Person],
+    policies: &[AdrPolicy],
+    overrides: &[AdrOverride],
+) -> (Vec<Person>, AdrReport) {
+    let mut report = AdrReport::default();
+    let mut ret = Vec::with_capacity(pers.len());
+    for per in pers {
+        let Some(adrs) = &per.adrs else {
+            ret.push(per.clone());
+            continue;
+        };
+        let ovr = overrides
+            .iter()
+            .find(|o| o.is_match(per))
+            .map(|o| o.select(adrs));
+        let (policy, (chosen, skipped)) = match ovr {
+            Some(sel) if !sel.0.is_empty() => ("override".to_string(), sel),
+            _ => {
+                if ovr.is_some() {
+                    report.unmatched.push(format!("{} ({})", per.name, per.org));
+                }
+                match policies.iter().find(|o| o.is_match(per)) {
+                    Some(policy) => (policy.to_string(), policy.select(adrs)),
+                    None => (AdrSelect::All.to_string(), (adrs.clone(), Vec::new())),
+                }
+            }
+        };
+        report.choices.push(AdrChoice {
+            name: per.name.clone(),
+            org: per.org.clone(),
+            policy,
+            chosen: chosen.clone(),
+            skipped,
+        });
+        ret.push(Person {
+            adrs: Some(chosen),
+            ..per.clone()
+        });
+    }
+    (ret, report)
+}
+
+/// Splits addresses into those at the indexes and the rest, keeping order.
+fn split_adrs(adrs: &[Address], idxs: &[usize]) -> (Vec<Address>, Vec<Address>) {
+    let (chosen, skipped): (Vec<_>, Vec<_>) = adrs
+        .iter()
+        .enumerate()
+        .partition(|(idx, _)| idxs.contains(idx));
+    (
+        chosen.into_iter().map(|(_, adr)| adr.clone()).collect(),
+        skipped.into_iter().map(|(_, adr)| adr.clone()).collect(),
+    )
+}
+
+/// Orders ZIP Codes by nearness: more shared leading digits, then a
+/// smaller difference.
+fn zip_distance(zip5: u32, ref_zip5: u32) -> (usize, u32) {
+    let a = format!("{:05}", zip5);
+    let b = format!("{:05}", ref_zip5);
+    let shared = a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+    (5 - shared, zip5.abs_diff(ref_zip5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    fn adrs() -> Vec<Address> {
+        vec![
+            address("2301 RAYBURN HOB", "DC", 20515),
+            address("1 MAIN ST", "CO", 80302),
+            address("2 MAIN ST", "CO", 80501),
+            address("3 MAIN ST", "CO", 81001),
+        ]
+    }
+
+    fn policy(select: AdrSelect, max: usize) -> AdrPolicy {
+        AdrPolicy {
+            select,
+            max,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_policy_select() {
+        let cases = vec![
+            (policy(AdrSelect::All, 0), vec![20515, 80302, 80501, 81001]),
+            (policy(AdrSelect::All, 2), vec![20515, 80302]),
+            (policy(AdrSelect::DcOnly, 0), vec![20515]),
+            (policy(AdrSelect::Nearest(80503), 0), vec![80501]),
+            (policy(AdrSelect::Nearest(80503), 2), vec![80302, 80501]),
+            (policy(AdrSelect::Nearest(20001), 0), vec![20515]),
+        ];
+
+        for (policy, expected) in cases {
+            let (chosen, skipped) = policy.select(&adrs());
+            let zips: Vec<u32> = chosen.iter().map(|o| o.zip5).collect();
+            assert_eq!(zips, expected, "{policy}");
+            assert_eq!(chosen.len() + skipped.len(), adrs().len(), "{policy}");
+        }
+
+        // DC only keeps the first address when none are in DC.
+        let (chosen, _) = policy(AdrSelect::DcOnly, 0).select(&adrs()[1..]);
+        assert_eq!(chosen, vec![adrs()[1].clone()]);
+    }
+
+    #[test]
+    fn test_select_adrs() {
+        let policies = vec![
+            AdrPolicy {
+                source: "house".into(),
+                ..policy(AdrSelect::DcOnly, 0)
+            },
+            AdrPolicy {
+                role: Some(Role::Political),
+                ..policy(AdrSelect::All, 2)
+            },
+        ];
+        let overrides = vec![
+            AdrOverride {
+                name: "J. \"Lou\" Correa".into(),
+                address1: vec!["2 Main St.".into(), "3 MAIN ST".into()],
+                ..Default::default()
+            },
+            // Matches no address.
+            AdrOverride {
+                name: "Jane Doe".into(),
+                address1: vec!["9 MAIN ST".into()],
+                ..Default::default()
+            },
+            // Matches another source.
+            AdrOverride {
+                source: "senate".into(),
+                name: "Mary Major".into(),
+                address1: vec!["1 MAIN ST".into()],
+            },
+        ];
+        let pers: Vec<Person> = [
+            ("Jane Doe", "house", Role::Political),
+            ("John Roe", "senate", Role::Political),
+            ("Lou Correa", "house", Role::Political),
+            ("Mary Major", "military", Role::Military),
+        ]
+        .into_iter()
+        .map(|(name, src, role)| Person {
+            src: src.into(),
+            role: Some(role),
+            ..person(name, adrs())
+        })
+        .collect();
+
+        let (ret, report) = select_adrs(&pers, &policies, &overrides);
+        let cnts: Vec<usize> = ret.iter().map(|o| o.adr_len()).collect();
+        assert_eq!(cnts, vec![1, 2, 2, 4]);
+        let policies: Vec<&str> = report.choices.iter().map(|o| o.policy.as_str()).collect();
+        assert_eq!(policies, vec!["DC only", "all max 2", "override", "all"]);
+        assert_eq!(report.choices[2].chosen[0].address1, "2 MAIN ST");
+        assert_eq!(report.choices[2].skipped.len(), 2);
+        assert_eq!(report.unmatched, vec!["Jane Doe ()"]);
+    }
+
+    #[test]
+    fn test_zip_distance() {
+        let cases = vec![
+            (80302, 80302, (0, 0)),
+            (80302, 80304, (1, 2)),
+            (80501, 80302, (3, 199)),
+            (20515, 80302, (5, 59787)),
+        ];
+
+        for (zip5, ref_zip5, expected) in cases {
+            assert_eq!(zip_distance(zip5, ref_zip5), expected);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::core::*;
+use crate::mailing::*;
+use crate::source::*;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DIR_ARCHIVE: &str = "archive";
+const FLE_NME_MANIFEST: &str = "manifest.json";
+
+/// A record of one archived mailing run.
+///
+/// Written once when the revision is created and never modified.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ArchiveManifest {
+    /// The mailing name, such as `2024-Q3`.
+    pub name: String,
+    /// Revisions start at 1 for each mailing name.
+    pub revision: u32,
+    pub created: DateTime<Utc>,
+    /// Input rosters and configuration.
+    pub inputs: Vec<ArchiveFile>,
+    pub trays: Vec<ArchiveTray>,
+    /// The mailing with envelope, letter and postage statement files.
+    pub outputs: Vec<ArchiveFile>,
+}
+
+/// An archived file and its SHA-256 hash.
+///
+/// The path is relative to the revision directory.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ArchiveFile {
+    pub path: String,
+    pub hash: String,
+}
+
+/// A tray and its mailpieces.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ArchiveTray {
+    pub name: String,
+    pub size: TraySize,
+    pub barcode_id: BarcodeId,
+    pub mailpieces: Vec<ArchivePiece>,
+}
+
+/// A mailpiece with its serial ID and barcode.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ArchivePiece {
+    pub id: u32,
+    pub barcode: String,
+    pub name: String,
+    pub routing_code: String,
+}
+
+/// Archives the mailing with its input rosters, configuration and output files.
+///
+/// Each call creates a new revision under `archive/<name>/`.
+/// Returns the revision directory.
+pub fn archive_mailing(mailing: &Mailing) -> Result<PathBuf> {
+    let dir = PathBuf::from(DIR_ARCHIVE).join(&mailing.name);
+    if is_dry_run() {
+        eprintln!("Dry run: skipping archive: {:?}", dir);
+        return Ok(dir);
+    }
+
+    // Gather input files.
+    let mut inputs: Vec<PathBuf> = sources()
+        .iter()
+        .map(|src| PathBuf::from(src.file_path()))
+        .filter(|pth| pth.exists())
+        .collect();
+    inputs.push(PathBuf::from(cfg_pth()));
+
+    archive_to(&dir, mailing, &inputs, &mailing.dir())
+}
+
+/// Archives a mailing as the next revision in a directory.
+fn archive_to(
+    dir: &Path,
+    mailing: &Mailing,
+    inputs: &[PathBuf],
+    out_dir: &Path,
+) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let revision = latest_revision(dir)? + 1;
+    let rev_dir = dir.join(revision_dir_name(revision));
+
+    // Fails when another run created the revision first.
+    fs::create_dir(&rev_dir)?;
+
+    let mut manifest = ArchiveManifest {
+        name: mailing.name.clone(),
+        revision,
+        created: Utc::now(),
+        inputs: Vec::with_capacity(inputs.len()),
+        trays: Vec::with_capacity(mailing.trays.len()),
+        outputs: Vec::new(),
+    };
+
+    // Copy inputs.
+    fs::create_dir(rev_dir.join("inputs"))?;
+    for pth in inputs {
+        let fle_nme = pth
+            .file_name()
+            .ok_or(anyhow!("invalid input path {:?}", pth))?;
+        let rel = Path::new("inputs").join(fle_nme);
+        manifest.inputs.push(copy_file(pth, &rev_dir, &rel)?);
+    }
+
+    // Record trays and mailpieces.
+    for tray in mailing.trays.iter() {
+        manifest.trays.push(ArchiveTray {
+            name: tray.name.clone(),
+            size: tray.size.clone(),
+            barcode_id: tray.barcode_id,
+            mailpieces: tray
+                .mailpieces
+                .iter()
+                .map(|mp| ArchivePiece {
+                    id: mp.id,
+                    barcode: mp.barcode.clone(),
+                    name: mp.name.clone(),
+                    routing_code: mp.routing_code(),
+                })
+                .collect(),
+        });
+    }
+
+    // Write mailing.
+    fs::create_dir(rev_dir.join("outputs"))?;
+    let rel = Path::new("outputs").join("mailing.json");
+    let bdy = serde_json::to_vec_pretty(mailing)?;
+    fs::write(rev_dir.join(&rel), &bdy)?;
+    manifest.outputs.push(ArchiveFile {
+        path: rel.to_string_lossy().into(),
+        hash: sha256(&bdy),
+    });
+
+    // Copy outputs.
+    if out_dir.exists() {
+        let mut pths: Vec<PathBuf> = fs::read_dir(out_dir)?
+            .map(|dir_ent| dir_ent.map(|dir_ent| dir_ent.path()))
+            .collect::<Result<_, _>>()?;
+        pths.sort_unstable();
+        for pth in pths.iter().filter(|pth| pth.is_file()) {
+            let rel = Path::new("outputs").join(pth.file_name().unwrap_or_default());
+            manifest.outputs.push(copy_file(pth, &rev_dir, &rel)?);
+        }
+    }
+
+    // Write manifest.
+    fs::write(
+        rev_dir.join(FLE_NME_MANIFEST),
+        serde_json::to_vec_pretty(&manifest)?,
+    )?;
+
+    // Make the revision read-only.
+    set_readonly(&rev_dir)?;
+
+    eprintln!(
+        "Archived {} revision {} to {:?}",
+        manifest.name, manifest.revision, rev_dir
+    );
+    Ok(rev_dir)
+}
+
+/// Reads the manifest of a revision directory.
+pub fn read_archive_manifest(rev_dir: &Path) -> Result<ArchiveManifest> {
+    read_from_file::<ArchiveManifest>(&rev_dir.join(FLE_NME_MANIFEST).to_string_lossy())
+}
+
+/// Returns the latest revision in a mailing archive directory, or 0.
+fn latest_revision(dir: &Path) -> Result<u32> {
+    let mut ret = 0;
+    for dir_ent in fs::read_dir(dir)? {
+        let fle_nme = dir_ent?.file_name().to_string_lossy().to_string();
+        if let Some(revision) = fle_nme
+            .strip_prefix('r')
+            .and_then(|rev| rev.parse::<u32>().ok())
+        {
+            ret = ret.max(revision);
+        }
+    }
+    Ok(ret)
+}
+
+fn revision_dir_name(revision: u32) -> String {
+    format!("r{revision:03}")
+}
+
+/// Copies a file into a revision directory and hashes it.
+fn copy_file(src: &Path, rev_dir: &Path, rel: &Path) -> Result<ArchiveFile> {
+    let bdy = fs::read(src).map_err(|err| anyhow!("unable to archive {:?}: {}", src, err))?;
+    fs::write(rev_dir.join(rel), &bdy)?;
+    Ok(ArchiveFile {
+        path: rel.to_string_lossy().into(),
+        hash: sha256(&bdy),
+    })
+}
+
+/// Sets every file in a directory tree read-only.
+fn set_readonly(dir: &Path) -> Result<()> {
+    for dir_ent in fs::read_dir(dir)? {
+        let pth = dir_ent?.path();
+        if pth.is_dir() {
+            set_readonly(&pth)?;
+        } else {
+            let mut perms = fs::metadata(&pth)?.permissions();
+            perms.set_readonly(true);
+            fs::set_permissions(&pth, perms)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::*;
+
+    #[test]
+    fn test_archive_revisions() {
+        let root = std::env::temp_dir().join(format!("adr_archive_{}", std::process::id()));
+        let out_dir = root.join("out");
+        fs::create_dir_all(&out_dir).unwrap();
+        let input = root.join("senate.json");
+        fs::write(&input, b"{}").unwrap();
+        fs::write(out_dir.join("A_1of01_cnt1_env.pdf"), b"pdf").unwrap();
+
+        let mut mailing = Mailing::new();
+        mailing.name = "2024-Q3".into();
+        mailing.trays.push(MailTray {
+            name: "A".into(),
+            size: TraySize::OneFoot,
+            barcode_id: BarcodeId::MixedAadc,
+            dest: Default::default(),
+            serial: 0,
+            mailpieces: vec![Mailpiece {
+                name: "Adams".into(),
+                zip5: 20510,
+                id: 7,
+                barcode: "ADFT".into(),
+                ..Default::default()
+            }],
+        });
+
+        let dir = root.join("archive").join(&mailing.name);
+        let inputs = vec![input];
+        let rev1 = archive_to(&dir, &mailing, &inputs, &out_dir).unwrap();
+        let rev2 = archive_to(&dir, &mailing, &inputs, &out_dir).unwrap();
+        assert_eq!(rev1, dir.join("r001"));
+        assert_eq!(rev2, dir.join("r002"));
+
+        let manifest = read_archive_manifest(&rev1).unwrap();
+        assert_eq!(manifest.revision, 1);
+        let paths: Vec<&str> = manifest.inputs.iter().map(|o| o.path.as_str()).collect();
+        assert_eq!(paths, vec!["inputs/senate.json"]);
+        assert_eq!(manifest.inputs[0].hash, sha256(b"{}"));
+        let paths: Vec<&str> = manifest.outputs.iter().map(|o| o.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["outputs/mailing.json", "outputs/A_1of01_cnt1_env.pdf"]
+        );
+        assert_eq!(manifest.outputs[1].hash, sha256(b"pdf"));
+        assert_eq!(manifest.trays[0].mailpieces[0].id, 7);
+        assert_eq!(manifest.trays[0].mailpieces[0].routing_code, "20510");
+
+        // Archived files are read-only.
+        let perms = fs::metadata(rev1.join(FLE_NME_MANIFEST))
+            .unwrap()
+            .permissions();
+        assert!(perms.readonly());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
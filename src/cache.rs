@@ -0,0 +1,476 @@
+use crate::core::*;
+use crate::executive::*;
+use crate::house::*;
+use crate::military::*;
+use crate::nasa::*;
+use crate::senate::*;
+use crate::state::*;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FLE_NME_MANIFEST: &str = "manifest.json";
+
+/// The cache policy key for USPS forms and publications.
+pub const KEY_USPS: &str = "usps";
+
+/// Days a cached response stays fresh when no policy matches its source.
+const MAX_AGE_DEFAULT: i64 = 60;
+
+/// Days a cached response stays fresh, by source key.
+///
+/// Rosters change with elections and appointments, so member pages
+/// expire within a quarter. USPS forms and publications rarely change.
+const MAX_AGES: &[(&str, i64)] = &[
+    (KEY_SENATE, 30),
+    (KEY_HOUSE, 30),
+    (KEY_STATE, 30),
+    (KEY_EXECUTIVE, 30),
+    (KEY_MILITARY, 30),
+    (KEY_NASA, 60),
+    (KEY_USPS, 365),
+];
+
+/// A cached response.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub url: String,
+    /// The key of the source that fetched the response, such as `senate`.
+    #[serde(default)]
+    pub source: String,
+    /// Time the response was fetched or last revalidated.
+    pub fetched: DateTime<Utc>,
+    /// HTTP status of the cached response.
+    pub status: u16,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// SHA-256 of the cached body.
+    pub hash: String,
+}
+
+impl CacheEntry {
+    /// Returns true when the entry is older than the max age for its source.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now - self.fetched > max_age(&self.source)
+    }
+
+    /// Sets validators from response headers.
+    fn set_validators(&mut self, headers: &HeaderMap) {
+        let hdr = |name| {
+            headers
+                .get(name)
+                .and_then(|val| val.to_str().ok())
+                .map(String::from)
+        };
+        if let Some(etag) = hdr(ETAG) {
+            self.etag = Some(etag);
+        }
+        if let Some(last_modified) = hdr(LAST_MODIFIED) {
+            self.last_modified = Some(last_modified);
+        }
+    }
+}
+
+/// Cache entries keyed by file name.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub entries: BTreeMap<String, CacheEntry>,
+}
+
+impl Manifest {
+    /// Reads the manifest from a cache directory.
+    ///
+    /// A missing manifest is empty.
+    pub fn read(dir: &Path) -> Result<Manifest> {
+        let pth = dir.join(FLE_NME_MANIFEST);
+        if !pth.exists() {
+            return Ok(Manifest::default());
+        }
+        let bdy = fs::read(&pth)?;
+        serde_json::from_slice(&bdy).map_err(|err| anyhow!("{:?}: {}", pth, err))
+    }
+
+    /// Writes the manifest to a cache directory.
+    ///
+    /// Writes a temporary file and renames it so an interrupted run
+    /// leaves the previous manifest intact.
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        let pth = dir.join(FLE_NME_MANIFEST);
+        let pth_tmp = pth.with_extension("json.tmp");
+        fs::write(&pth_tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&pth_tmp, &pth)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let now = Utc::now();
+        for ent in self.entries.values() {
+            let age = (now - ent.fetched).num_days();
+            let state = if ent.is_expired(now) {
+                "expired"
+            } else {
+                "fresh"
+            };
+            writeln!(f, "{} {:>4}d {:<7} {}", ent.status, age, state, ent.url)?;
+        }
+        write!(f, "{} entries", self.entries.len())
+    }
+}
+
+/// Fetches a URL into the cache and returns the cached file path.
+///
+/// `key` is the source key that selects the max age. Fresh entries are read from disk. Expired entries are revalidated
+/// with the stored ETag and Last-Modified validators. A stale file is
+/// used when the network is unavailable or the server returns an error.
+pub async fn fetch_cached(url: &str, key: &str) -> Result<PathBuf> {
+    let dir = cache_dir();
+
+    // Create the cache directory if it does not exist
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    let mut mfst = Manifest::read(&dir)?;
+    let fle_nme = url_to_filename(url);
+    let pth = dir.join(&fle_nme);
+    let now = Utc::now();
+
+    // Check if a fresh cache file exists
+    let ent = match mfst.entries.get(&fle_nme) {
+        Some(ent) if pth.exists() => Some(CacheEntry {
+            source: key.into(),
+            ..ent.clone()
+        }),
+        _ => None,
+    };
+    if let Some(ent) = &ent {
+        if !ent.is_expired(now) {
+            eprintln!("Loading cached {:?}...", &pth);
+            return Ok(pth);
+        }
+    }
+
+    // Request conditionally when a cached file exists.
+    let mut req = CLI.get(url);
+    if let Some(ent) = &ent {
+        eprintln!("Revalidating {url:?}...");
+        if let Some(etag) = &ent.etag {
+            req = req.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &ent.last_modified {
+            req = req.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    } else {
+        eprintln!("Fetching {url:?}...");
+    }
+    let res = match req.send().await {
+        Ok(res) => res,
+        Err(err) if pth.exists() => {
+            eprintln!("err: fetch {url:?}: {err}; using stale cache");
+            return Ok(pth);
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let ent = match ent {
+        Some(mut ent) if res.status() == StatusCode::NOT_MODIFIED => {
+            ent.fetched = now;
+            ent.set_validators(res.headers());
+            ent
+        }
+        _ => {
+            let status = res.status();
+            let headers = res.headers().clone();
+            let bdy = res.bytes().await?;
+            match cache_response(&pth, url, key, now, status, &headers, &bdy)? {
+                Some(ent) => ent,
+                None => return Ok(pth),
+            }
+        }
+    };
+    mfst.entries.insert(fle_nme, ent);
+    mfst.write(&dir)?;
+
+    Ok(pth)
+}
+
+/// Saves a fetched body to the cache file and returns its entry.
+///
+/// Only successful responses are cached. An error response keeps a
+/// stale file and its entry, and returns None.
+fn cache_response(
+    pth: &Path,
+    url: &str,
+    key: &str,
+    now: DateTime<Utc>,
+    status: StatusCode,
+    headers: &HeaderMap,
+    bdy: &[u8],
+) -> Result<Option<CacheEntry>> {
+    if !status.is_success() {
+        if pth.exists() {
+            eprintln!("err: fetch {url:?}: {status}; using stale cache");
+            return Ok(None);
+        }
+        return Err(anyhow!("fetch {url:?}: {status}"));
+    }
+
+    // Save the fetched body to the cache file
+    fs::write(pth, bdy)?;
+
+    let mut ent = CacheEntry {
+        url: url.into(),
+        source: key.into(),
+        fetched: now,
+        status: status.as_u16(),
+        etag: None,
+        last_modified: None,
+        hash: sha256(bdy),
+    };
+    ent.set_validators(headers);
+    Ok(Some(ent))
+}
+
+/// Prints cache entries.
+pub fn cache_ls() -> Result<()> {
+    println!("{}", Manifest::read(&cache_dir())?);
+    Ok(())
+}
+
+/// Removes expired entries and untracked files from the cache.
+///
+/// Removes every entry when `all` is true.
+pub fn cache_purge(all: bool) -> Result<()> {
+    let dir = cache_dir();
+    if !dir.exists() {
+        return Ok(());
+    }
+    let mut mfst = Manifest::read(&dir)?;
+    let now = Utc::now();
+
+    // Find files to remove.
+    let mut fle_nmes: Vec<String> = mfst
+        .entries
+        .iter()
+        .filter(|(_, ent)| all || ent.is_expired(now))
+        .map(|(fle_nme, _)| fle_nme.clone())
+        .collect();
+    fle_nmes.extend(untracked(&dir, &mfst)?);
+
+    for fle_nme in &fle_nmes {
+        if is_dry_run() {
+            eprintln!("Dry run: skipping purge: {}", fle_nme);
+            continue;
+        }
+        let pth = dir.join(fle_nme);
+        if pth.exists() {
+            fs::remove_file(pth)?;
+        }
+        mfst.entries.remove(fle_nme);
+    }
+    eprintln!("{} files purged", fle_nmes.len());
+
+    if !is_dry_run() {
+        mfst.write(&dir)?;
+    }
+    Ok(())
+}
+
+/// Checks each cached file against its recorded hash.
+///
+/// Reports missing, modified and untracked files.
+pub fn cache_verify() -> Result<()> {
+    let dir = cache_dir();
+    let problems = verify(&dir, &Manifest::read(&dir)?)?;
+    for problem in &problems {
+        eprintln!("{problem}");
+    }
+    if !problems.is_empty() {
+        return Err(anyhow!("{} cache problems", problems.len()));
+    }
+    Ok(())
+}
+
+/// Returns a description of each cache problem.
+fn verify(dir: &Path, mfst: &Manifest) -> Result<Vec<String>> {
+    let mut ret = Vec::new();
+    for (fle_nme, ent) in &mfst.entries {
+        let pth = dir.join(fle_nme);
+        if !pth.exists() {
+            ret.push(format!("missing: {}", ent.url));
+        } else if sha256(&fs::read(&pth)?) != ent.hash {
+            ret.push(format!("modified: {}", ent.url));
+        }
+    }
+    for fle_nme in untracked(dir, mfst)? {
+        ret.push(format!("untracked: {}", fle_nme));
+    }
+    Ok(ret)
+}
+
+/// Returns names of cache files without a manifest entry.
+fn untracked(dir: &Path, mfst: &Manifest) -> Result<Vec<String>> {
+    let mut ret = Vec::new();
+    if !dir.exists() {
+        return Ok(ret);
+    }
+    for dir_ent in fs::read_dir(dir)? {
+        let fle_nme = dir_ent?.file_name().to_string_lossy().to_string();
+        if !fle_nme.starts_with(FLE_NME_MANIFEST) && !mfst.entries.contains_key(&fle_nme) {
+            ret.push(fle_nme);
+        }
+    }
+    ret.sort_unstable();
+    Ok(ret)
+}
+
+/// Returns the max age for a source key.
+pub fn max_age(key: &str) -> Duration {
+    let days = MAX_AGES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map_or(MAX_AGE_DEFAULT, |(_, days)| *days);
+    Duration::days(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str, age_days: i64) -> CacheEntry {
+        CacheEntry {
+            url: url.into(),
+            source: "".into(),
+            fetched: Utc::now() - Duration::days(age_days),
+            status: 200,
+            etag: None,
+            last_modified: None,
+            hash: sha256(b"body"),
+        }
+    }
+
+    #[test]
+    fn test_max_age() {
+        let cases = vec![
+            (KEY_SENATE, 30),
+            (KEY_EXECUTIVE, 30),
+            (KEY_NASA, 60),
+            (KEY_USPS, 365),
+            ("", MAX_AGE_DEFAULT),
+            // A source key, not a host, selects the policy.
+            ("senate.gov", MAX_AGE_DEFAULT),
+        ];
+
+        for (key, days) in cases {
+            assert_eq!(max_age(key), Duration::days(days), "{key}");
+        }
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let now = Utc::now();
+        let cases = vec![
+            (KEY_SENATE, 29, false),
+            (KEY_SENATE, 31, true),
+            (KEY_USPS, 300, false),
+            // Entries cached before source keys use the default.
+            ("", 59, false),
+            ("", 61, true),
+        ];
+
+        for (key, age_days, expected) in cases {
+            let ent = CacheEntry {
+                source: key.into(),
+                ..entry("https://www.state.gov", age_days)
+            };
+            assert_eq!(ent.is_expired(now), expected, "{key} {age_days}");
+        }
+    }
+
+    #[test]
+    fn test_cache_response() {
+        let dir = std::env::temp_dir().join(format!("adr_cache_res_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let pth = dir.join("page");
+        let url = "https://a.gov/page";
+        let now = Utc::now();
+        let headers = HeaderMap::new();
+
+        // An error response without a cached file fails.
+        let res = cache_response(
+            &pth,
+            url,
+            KEY_SENATE,
+            now,
+            StatusCode::NOT_FOUND,
+            &headers,
+            b"err",
+        );
+        assert!(res.is_err());
+        assert!(!pth.exists());
+
+        // A success response is cached.
+        let ent = cache_response(
+            &pth,
+            url,
+            KEY_SENATE,
+            now,
+            StatusCode::OK,
+            &headers,
+            b"body",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(ent.hash, sha256(b"body"));
+        assert_eq!(ent.source, KEY_SENATE);
+
+        // An error response leaves the cached file unchanged.
+        let cases = vec![StatusCode::NOT_FOUND, StatusCode::INTERNAL_SERVER_ERROR];
+        for status in cases {
+            let res = cache_response(&pth, url, KEY_SENATE, now, status, &headers, b"err");
+            assert_eq!(res.unwrap(), None, "{status}");
+            assert_eq!(fs::read(&pth).unwrap(), b"body", "{status}");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_verify() {
+        let dir = std::env::temp_dir().join(format!("adr_cache_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut mfst = Manifest::default();
+        mfst.entries
+            .insert("ok".into(), entry("https://a.gov/ok", 0));
+        mfst.entries
+            .insert("modified".into(), entry("https://a.gov/modified", 0));
+        mfst.entries
+            .insert("missing".into(), entry("https://a.gov/missing", 0));
+        fs::write(dir.join("ok"), b"body").unwrap();
+        fs::write(dir.join("modified"), b"changed").unwrap();
+        fs::write(dir.join("untracked"), b"body").unwrap();
+        mfst.write(&dir).unwrap();
+
+        // Manifest round trips.
+        assert_eq!(Manifest::read(&dir).unwrap(), mfst);
+
+        let problems = verify(&dir, &mfst).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            problems,
+            vec![
+                "missing: https://a.gov/missing",
+                "modified: https://a.gov/modified",
+                "untracked: untracked",
+            ]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: adr [OPTIONS] [COMMAND]
+
+Commands:
+  fetch <source>  Fetch a roster from network and write it to disk
+                  (military, nasa, executive, senate, house, state, all)
+  parse           Load rosters, parsing any missing addresses, and report gaps
+  standardize     Standardize roster addresses with the USPS
+  presort         Create mailpieces and pre-sort them into trays
+  barcode         Encode and verify mailpiece barcodes
+  render          Create envelope, letter and tray label PDFs
+  labels          Create the tray label PDF
+  statement       Fill in the postage statement PDF
+  report          Print a mailing summary
+  lists           List labeling lists and flag stale lists
+  ledger          List serial ID allocations and check for overlaps
+  archive         Archive the mailing, its inputs and outputs as a new revision
+  diff [old] [new]
+                  Compare roster snapshots by mailing name, such as 2024-Q3
+                  (defaults to the previous snapshot and current rosters)
+  cache ls        List cached responses and their age
+  cache purge     Remove expired and untracked cache files
+                  (--all removes every file)
+  cache verify    Check cached files against their recorded hashes
+  all             Run every stage (default)
+
+Options:
+  -d, --dir <path>  Working directory for rosters, caches and mailings
+  -c, --cfg <path>  Mailing configuration file [default: mailing_cfg.json]
+  -n, --dry-run     Run without writing files
+      --json        Print the diff report as JSON
+  -h, --help        Print help";
+
+/// Command-line arguments.
+#[derive(Debug, Default, PartialEq)]
+pub struct Cli {
+    /// Working directory for rosters, caches and mailings.
+    pub dir: Option<PathBuf>,
+    /// Mailing configuration file.
+    pub cfg: Option<PathBuf>,
+    /// Run without writing files.
+    pub dry_run: bool,
+    /// Print the diff report as JSON.
+    pub json: bool,
+    pub cmd: Cmd,
+}
+
+/// A pipeline stage to run.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Cmd {
+    #[default]
+    All,
+    Fetch(String),
+    Parse,
+    Standardize,
+    Presort,
+    Barcode,
+    Render,
+    Labels,
+    Statement,
+    Report,
+    Lists,
+    Ledger,
+    Archive,
+    Diff {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Cache(CacheCmd),
+    Help,
+}
+
+/// A cache maintenance command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheCmd {
+    Ls,
+    Purge { all: bool },
+    Verify,
+}
+
+impl Cli {
+    /// Parses command-line arguments, excluding the program name.
+    pub fn parse<I>(args: I) -> Result<Cli>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut ret = Cli::default();
+        let mut cmd: Option<Cmd> = None;
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-d" | "--dir" => {
+                    let val = args.next().ok_or(anyhow!("missing path for {arg}"))?;
+                    ret.dir = Some(PathBuf::from(val));
+                }
+                "-c" | "--cfg" => {
+                    let val = args.next().ok_or(anyhow!("missing path for {arg}"))?;
+                    ret.cfg = Some(PathBuf::from(val));
+                }
+                "-n" | "--dry-run" => ret.dry_run = true,
+                "--json" => ret.json = true,
+                "-h" | "--help" => cmd = Some(Cmd::Help),
+                s if s.starts_with('-') => return Err(anyhow!("unknown option {s}")),
+                s if cmd.is_some() => return Err(anyhow!("unexpected argument {s}")),
+                "fetch" => {
+                    let src = args.next().ok_or(anyhow!("missing source for fetch"))?;
+                    cmd = Some(Cmd::Fetch(src));
+                }
+                "parse" => cmd = Some(Cmd::Parse),
+                "standardize" => cmd = Some(Cmd::Standardize),
+                "presort" => cmd = Some(Cmd::Presort),
+                "barcode" => cmd = Some(Cmd::Barcode),
+                "render" => cmd = Some(Cmd::Render),
+                "labels" => cmd = Some(Cmd::Labels),
+                "statement" => cmd = Some(Cmd::Statement),
+                "report" => cmd = Some(Cmd::Report),
+                "lists" => cmd = Some(Cmd::Lists),
+                "ledger" => cmd = Some(Cmd::Ledger),
+                "archive" => cmd = Some(Cmd::Archive),
+                "diff" => {
+                    let old = args.next_if(|arg| !arg.starts_with('-'));
+                    let new = args.next_if(|arg| !arg.starts_with('-'));
+                    cmd = Some(Cmd::Diff { old, new });
+                }
+                "cache" => {
+                    let sub = args.next().ok_or(anyhow!("missing command for cache"))?;
+                    let cache_cmd = match sub.as_str() {
+                        "ls" => CacheCmd::Ls,
+                        "purge" => {
+                            let all = args.next_if(|arg| arg == "--all").is_some();
+                            CacheCmd::Purge { all }
+                        }
+                        "verify" => CacheCmd::Verify,
+                        s => return Err(anyhow!("unknown cache command {s}")),
+                    };
+                    cmd = Some(Cmd::Cache(cache_cmd));
+                }
+                "all" => cmd = Some(Cmd::All),
+                s => return Err(anyhow!("unknown command {s}")),
+            }
+        }
+        ret.cmd = cmd.unwrap_or_default();
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_default() {
+        assert_eq!(Cli::parse(args("")).unwrap(), Cli::default());
+    }
+
+    #[test]
+    fn test_parse_cmds() {
+        let cases = vec![
+            ("all", Cmd::All),
+            ("fetch senate", Cmd::Fetch("senate".into())),
+            ("parse", Cmd::Parse),
+            ("standardize", Cmd::Standardize),
+            ("presort", Cmd::Presort),
+            ("barcode", Cmd::Barcode),
+            ("render", Cmd::Render),
+            ("labels", Cmd::Labels),
+            ("statement", Cmd::Statement),
+            ("report", Cmd::Report),
+            ("lists", Cmd::Lists),
+            ("ledger", Cmd::Ledger),
+            ("archive", Cmd::Archive),
+            (
+                "diff",
+                Cmd::Diff {
+                    old: None,
+                    new: None,
+                },
+            ),
+            (
+                "diff 2024-Q3",
+                Cmd::Diff {
+                    old: Some("2024-Q3".into()),
+                    new: None,
+                },
+            ),
+            (
+                "diff 2024-Q3 2024-Q4",
+                Cmd::Diff {
+                    old: Some("2024-Q3".into()),
+                    new: Some("2024-Q4".into()),
+                },
+            ),
+            ("cache ls", Cmd::Cache(CacheCmd::Ls)),
+            ("cache purge", Cmd::Cache(CacheCmd::Purge { all: false })),
+            (
+                "cache purge --all",
+                Cmd::Cache(CacheCmd::Purge { all: true }),
+            ),
+            ("cache verify", Cmd::Cache(CacheCmd::Verify)),
+            ("--help", Cmd::Help),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(Cli::parse(args(input)).unwrap().cmd, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let cli = Cli::parse(args("--dir work -c cfg.json -n render")).unwrap();
+        assert_eq!(
+            cli,
+            Cli {
+                dir: Some(PathBuf::from("work")),
+                cfg: Some(PathBuf::from("cfg.json")),
+                dry_run: true,
+                json: false,
+                cmd: Cmd::Render,
+            }
+        );
+
+        // Options may follow the command.
+        let cli = Cli::parse(args("presort --dry-run")).unwrap();
+        assert!(cli.dry_run);
+        assert_eq!(cli.cmd, Cmd::Presort);
+
+        let cli = Cli::parse(args("diff 2024-Q3 --json")).unwrap();
+        assert!(cli.json);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let cases = vec![
+            "fetch",
+            "--dir",
+            "--cfg",
+            "--bogus",
+            "bogus",
+            "render presort",
+            "cache",
+            "cache bogus",
+            "diff a b c",
+            "report --all",
+        ];
+
+        for input in cases {
+            assert!(Cli::parse(args(input)).is_err(), "{input}");
+        }
+    }
+}
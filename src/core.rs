@@ -1,3 +1,4 @@
+use crate::cache::*;
 use crate::models::*;
 use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
@@ -6,11 +7,13 @@ use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
 use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs;
 use std::fs::File;
 use std::io::{self, Write};
 use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 lazy_static! {
     pub static ref CLI: Client = {
@@ -26,12 +29,35 @@ lazy_static! {
     };
 }
 
+/// Indicates whether the pipeline runs without writing files.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether the pipeline runs without writing files.
+pub fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
+
+/// Returns true when the pipeline runs without writing files.
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
 /// Serializes a JSON struct to a file.
+///
+/// Writes a temporary file and renames it so an interrupted write
+/// leaves the previous file intact. Skips writing during a dry run.
 pub fn write_to_file<T: Serialize>(data: &T, file_path: &str) -> Result<()> {
+    if is_dry_run() {
+        eprintln!("Dry run: skipping file: {}", file_path);
+        return Ok(());
+    }
     eprintln!("Writing file: {}", file_path);
-    let file = File::create(file_path)?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer_pretty(writer, &data)?;
+    let tmp_pth = format!("{}.tmp", file_path);
+    let file = File::create(&tmp_pth)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, &data)?;
+    writer.into_inner()?.sync_all()?;
+    fs::rename(&tmp_pth, file_path)?;
     Ok(())
 }
 
@@ -49,61 +75,21 @@ pub fn cache_dir() -> PathBuf {
 }
 
 /// Fetches HTML from a URL and caches the response body to a local file.
-pub async fn fetch_html(url: &str) -> Result<String> {
-    let mut pth = cache_dir();
-
-    // Create the cache directory if it does not exist
-    if !pth.exists() {
-        fs::create_dir_all(&pth)?;
-    }
-
-    // Check if the cache file exists
-    pth.push(url_to_filename(url));
-    if pth.exists() {
-        eprintln!("Loading cached HTML from {:?}...", &pth);
-        let cached_body = fs::read_to_string(&pth)?;
-        return Ok(cached_body);
-    }
-
-    eprintln!("Fetching {url:?}...");
-    let res = CLI.get(url).send().await?;
-    let bdy = res.text().await?;
-
-    // Save the fetched body to the cache file
-    let mut file = fs::File::create(&pth)?;
-    file.write_all(bdy.as_bytes())?;
-
-    Ok(bdy)
+///
+/// `key` is the source key that selects the cache max age.
+pub async fn fetch_html(url: &str, key: &str) -> Result<String> {
+    let pth = fetch_cached(url, key).await?;
+    let bdy = fs::read(&pth)?;
+    Ok(String::from_utf8_lossy(&bdy).into_owned())
 }
 
 /// Fetches PDF from a URL and caches the response body to a local file.
-pub async fn fetch_pdf(url: &str) -> Result<PathBuf> {
-    let mut pth = cache_dir();
-
-    // Create the cache directory if it does not exist
-    if !pth.exists() {
-        fs::create_dir_all(&pth)?;
-    }
-
-    // Check if the cache file exists
-    pth.push(url_to_filename(url));
-    if pth.exists() {
-        return Ok(pth);
-    }
-
-    eprintln!("Fetching {url:?}...");
-    let res = CLI.get(url).send().await?;
-    let bdy = res.bytes().await?;
-
-    // Save the fetched body to the cache file
-    let mut file = fs::File::create(&pth)?;
-    file.write_all(&bdy)?;
-
-    Ok(pth)
+pub async fn fetch_pdf(url: &str, key: &str) -> Result<PathBuf> {
+    fetch_cached(url, key).await
 }
 
 /// Converts a URL to a safe filename by replacing non-alphanumeric characters.
-fn url_to_filename(url: &str) -> String {
+pub fn url_to_filename(url: &str) -> String {
     // Skip https://
     url[8..]
         .chars()
@@ -111,6 +97,14 @@ fn url_to_filename(url: &str) -> String {
         .collect()
 }
 
+/// Returns the hex SHA-256 digest of bytes.
+pub fn sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
 /// Transforms a String to Option<String>.
 /// Empty string is None.
 pub fn string_to_opt(s: String) -> Option<String> {
@@ -169,6 +163,14 @@ mod tests {
         assert_eq!(numfmt(1000000000), "1,000,000,000");
     }
     
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
     #[test]
     fn test_valid_cases() {
         let test_cases = vec![
@@ -222,13 +224,13 @@ mod tests {
         let test_url = "https://www.google.com";
 
         // First call should fetch and cache the content
-        let result = runtime.block_on(fetch_html(test_url));
+        let result = runtime.block_on(fetch_html(test_url, ""));
         assert!(result.is_ok());
         let body = result.unwrap();
         assert!(!body.is_empty());
 
         // Second call should load from cache
-        let result = runtime.block_on(fetch_html(test_url));
+        let result = runtime.block_on(fetch_html(test_url, ""));
         assert!(result.is_ok());
         let cached_body = result.unwrap();
         assert_eq!(body, cached_body);
@@ -0,0 +1,405 @@
+use crate::core::*;
+use crate::models::*;
+use crate::prsr::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+const FLE_PTH: &str = "merge_report.json";
+
+/// The most recipients on one household mailpiece.
+///
+/// Keeps the name line within the address block. Others at the delivery
+/// point, such as an office building, keep their own mailpieces.
+pub const HOUSEHOLD_MAX: usize = 3;
+
+/// Mailpieces merged into one.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Merge {
+    /// The recipient name line of the kept mailpiece.
+    pub name: String,
+    /// The delivery address, such as `100 MAIN ST,WASHINGTON,DC,20510,1`.
+    pub address: String,
+    /// The organization of the kept mailpiece.
+    pub kept: String,
+    /// The organizations of the dropped mailpieces.
+    pub dropped: Vec<String>,
+    /// The recipients of a household mailpiece.
+    ///
+    /// Empty for a duplicate recipient.
+    pub household: Vec<String>,
+}
+
+/// Duplicate recipients and households merged before presort.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MergeReport {
+    pub mailpiece_cnt: usize,
+    pub duplicates: Vec<Merge>,
+    pub households: Vec<Merge>,
+}
+
+impl MergeReport {
+    /// Mailpieces removed by merging.
+    pub fn removed_cnt(&self) -> usize {
+        self.duplicates
+            .iter()
+            .chain(self.households.iter())
+            .map(|o| o.dropped.len())
+            .sum()
+    }
+
+    /// Writes the report to disk.
+    pub fn save(&self) -> Result<()> {
+        write_to_file(self, FLE_PTH)
+    }
+}
+
+impl fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "merged {} of {} mailpieces: {} duplicates, {} households",
+            self.removed_cnt(),
+            self.mailpiece_cnt,
+            self.duplicates.len(),
+            self.households.len()
+        )?;
+        for mrg in self.duplicates.iter() {
+            write!(
+                f,
+                "\n  = {} ({}; dropped {}) {}",
+                mrg.name,
+                mrg.kept,
+                mrg.dropped.join(", "),
+                mrg.address
+            )?;
+        }
+        for mrg in self.households.iter() {
+            write!(
+                f,
+                "\n  + {} ({}) {}",
+                mrg.name,
+                mrg.household.join("; "),
+                mrg.address
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Merges mailpieces for the same recipient at the same address.
+///
+/// Recipients match on their normalized name and standardized address.
+/// Middle initials must match when both names have one. When one name
+/// and address has several middle initials, a name without one is
+/// ambiguous and merges only with names without one.
+/// The first mailpiece is kept, in source order. With `household`, up to
+/// `HOUSEHOLD_MAX` recipients at one delivery point also share one
+/// mailpiece.
+pub fn merge_mailpieces(mps: Vec<Mailpiece>, household: bool) -> (Vec<Mailpiece>, MergeReport) {
+    let mut report = MergeReport {
+        mailpiece_cnt: mps.len(),
+        ..Default::default()
+    };
+
+    // Find the middle initials of each name and address.
+    let mut initials: HashMap<((String, String), String), HashSet<char>> = HashMap::new();
+    for mp in mps.iter() {
+        let nme = name_parts(mp);
+        if let Some(initial) = nme.middle_initial() {
+            initials
+                .entry((nme.key(), adr_key(mp)))
+                .or_default()
+                .insert(initial);
+        }
+    }
+
+    // Merge duplicate recipients.
+    let mut ret: Vec<Mailpiece> = Vec::with_capacity(mps.len());
+    let mut idxs: HashMap<((String, String), String), Vec<usize>> = HashMap::new();
+    for mp in mps {
+        let nme = name_parts(&mp);
+        let key = (nme.key(), adr_key(&mp));
+        let is_ambiguous = initials.get(&key).is_some_and(|o| o.len() > 1);
+        let cands = idxs.entry(key).or_default();
+        match cands.iter().copied().find(|idx| {
+            let othr = name_parts(&ret[*idx]);
+            if is_ambiguous {
+                othr.middle_initial() == nme.middle_initial()
+            } else {
+                othr.is_match(&nme)
+            }
+        }) {
+            Some(idx) => {
+                let kept = &mut ret[idx];
+                // Fill missing titles from the duplicate.
+                if kept.title1.is_none() {
+                    kept.title1.clone_from(&mp.title1);
+                    kept.title2.clone_from(&mp.title2);
+                }
+                let mrg = match report
+                    .duplicates
+                    .iter_mut()
+                    .find(|o| o.name == kept.name && o.address == adr_lne(kept))
+                {
+                    Some(mrg) => mrg,
+                    None => {
+                        report.duplicates.push(Merge {
+                            name: kept.name.clone(),
+                            address: adr_lne(kept),
+                            kept: kept.org.clone().unwrap_or_default(),
+                            ..Default::default()
+                        });
+                        report.duplicates.last_mut().unwrap()
+                    }
+                };
+                mrg.dropped.push(mp.org.unwrap_or_default());
+            }
+            None => {
+                cands.push(ret.len());
+                ret.push(mp);
+            }
+        }
+    }
+    if !household {
+        return (ret, report);
+    }
+
+    // Collapse recipients at one delivery point.
+    let mut grps: Vec<Vec<Mailpiece>> = Vec::new();
+    let mut idxs: HashMap<String, usize> = HashMap::new();
+    for mp in ret {
+        let key = adr_key(&mp);
+        match idxs.get(&key) {
+            Some(&idx) => grps[idx].push(mp),
+            None => {
+                idxs.insert(key, grps.len());
+                grps.push(vec![mp]);
+            }
+        }
+    }
+    let mut ret = Vec::with_capacity(grps.len());
+    for mut grp in grps {
+        let rest = grp.split_off(grp.len().min(HOUSEHOLD_MAX));
+        if grp.len() == 1 {
+            ret.extend(grp);
+            ret.extend(rest);
+            continue;
+        }
+        let names: Vec<String> = grp.iter().map(|o| o.name.clone()).collect();
+        let mut mp = grp[0].clone();
+        mp.name = household_name(&names);
+        mp.name_parts = PersonName::default();
+        // Address the household by name only.
+        mp.title1 = None;
+        mp.title2 = None;
+        mp.org = None;
+        mp.role = None;
+        mp.src = String::new();
+        report.households.push(Merge {
+            name: mp.name.clone(),
+            address: adr_lne(&mp),
+            kept: grp[0].org.clone().unwrap_or_default(),
+            dropped: grp[1..]
+                .iter()
+                .map(|o| o.org.clone().unwrap_or_default())
+                .collect(),
+            household: names,
+        });
+        ret.push(mp);
+        ret.extend(rest);
+    }
+
+    (ret, report)
+}
+
+/// The name parts, parsed from the name when the mailpiece has none.
+fn name_parts(mp: &Mailpiece) -> PersonName {
+    if mp.name_parts.is_empty() {
+        name_parse(&mp.name)
+    } else {
+        mp.name_parts.clone()
+    }
+}
+
+/// The standardized delivery address key: the routing code and the
+/// normalized first address line.
+fn adr_key(mp: &Mailpiece) -> String {
+    let address1 = dot_remove(mp.address1.to_uppercase())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{} {}", mp.routing_code(), address1)
+}
+
+/// The delivery address on one line.
+fn adr_lne(mp: &Mailpiece) -> String {
+    format!(
+        "{},{},{},{:05},{}",
+        mp.address1, mp.city, mp.state, mp.zip5, mp.zip4
+    )
+}
+
+/// The household name line, such as `Jane Doe and John Smith`.
+fn household_name(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => name.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    /// Mailpieces from name, organization, address and delivery point rows.
+    fn mailpieces(rows: &[(&str, &str, &str, &str)]) -> Vec<Mailpiece> {
+        rows.iter()
+            .map(|(name, org, address1, delivery_point)| Mailpiece {
+                title1: Some(format!("{org} title")),
+                address1: address1.to_string(),
+                delivery_point: Some(delivery_point.to_string()),
+                org: Some(org.to_string()),
+                role: Some(Role::Scientific),
+                ..mailpiece(name)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_duplicates() {
+        let mps = mailpieces(&[
+            ("Jane A. Doe", "NASA", "300 E ST SW", "00"),
+            ("Jane Doe", "NASA ARMD", "300 E. St.  SW", "00"),
+            ("Jane Doe", "NASA SMD", "300 E ST SW", "00"),
+            ("John Doe", "NASA", "300 E ST SW", "00"),
+            ("Jane Doe", "NASA JSC", "2101 NASA PKWY", "01"),
+        ]);
+
+        let (ret, report) = merge_mailpieces(mps, false);
+        let names: Vec<(&str, &str)> = ret
+            .iter()
+            .map(|o| (o.name.as_str(), o.address1.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Jane A. Doe", "300 E ST SW"),
+                ("John Doe", "300 E ST SW"),
+                ("Jane Doe", "2101 NASA PKWY"),
+            ]
+        );
+        assert_eq!(report.mailpiece_cnt, 5);
+        assert_eq!(report.removed_cnt(), 2);
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].kept, "NASA");
+        assert_eq!(report.duplicates[0].dropped, vec!["NASA ARMD", "NASA SMD"]);
+        assert!(report.households.is_empty());
+    }
+
+    #[test]
+    fn test_merge_duplicates_initials() {
+        // A name without a middle initial is ambiguous when the same name
+        // at the address has several initials.
+        let mps = mailpieces(&[
+            ("Jane Doe", "NASA", "300 E ST SW", "00"),
+            ("Jane A. Doe", "NASA ARMD", "300 E ST SW", "00"),
+            ("Jane B. Doe", "NASA SMD", "300 E ST SW", "00"),
+            ("Jane Doe", "NASA JSC", "300 E ST SW", "00"),
+            ("Jane Ann Doe", "NASA GSFC", "300 E ST SW", "00"),
+        ]);
+
+        let (ret, report) = merge_mailpieces(mps, false);
+        let names: Vec<&str> = ret.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, vec!["Jane Doe", "Jane A. Doe", "Jane B. Doe"]);
+        let dropped: Vec<Vec<String>> = report
+            .duplicates
+            .iter()
+            .map(|o| o.dropped.clone())
+            .collect();
+        assert_eq!(dropped, vec![vec!["NASA JSC"], vec!["NASA GSFC"]]);
+
+        // With one initial, a name without one merges.
+        let mps = mailpieces(&[
+            ("Jane Doe", "NASA", "300 E ST SW", "00"),
+            ("Jane A. Doe", "NASA ARMD", "300 E ST SW", "00"),
+        ]);
+        let (ret, _) = merge_mailpieces(mps, false);
+        assert_eq!(ret.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_households() {
+        let mps = mailpieces(&[
+            ("Jane Doe", "NASA", "300 E ST SW", "00"),
+            ("John Roe", "NASA", "300 E ST SW", "00"),
+            ("Jane Doe", "NASA", "300 E ST SW", "00"),
+            ("Mary Major", "NASA", "300 E ST SW", "02"),
+        ]);
+
+        let (ret, report) = merge_mailpieces(mps, true);
+        assert_eq!(ret.len(), 2);
+        assert_eq!(ret[0].name, "Jane Doe and John Roe");
+        assert_eq!(ret[0].title1, None);
+        assert_eq!(ret[0].role, None);
+        assert_eq!(ret[0].org, None);
+        assert_eq!(ret[1].name, "Mary Major");
+        assert_eq!(report.removed_cnt(), 2);
+        assert_eq!(report.households.len(), 1);
+        assert_eq!(report.households[0].household, vec!["Jane Doe", "John Roe"]);
+    }
+
+    #[test]
+    fn test_merge_households_max() {
+        let names = [
+            "Jane Doe",
+            "John Roe",
+            "Mary Major",
+            "Richard Miles",
+            "Ann Other",
+            "Sam Smith",
+            "Lee Jones",
+            "Kim Park",
+            "Pat Lee",
+            "Max Moss",
+        ];
+        let rows: Vec<_> = names
+            .iter()
+            .map(|name| (*name, "NASA", "300 E ST SW", "00"))
+            .collect();
+        let mps = mailpieces(&rows);
+
+        let (ret, report) = merge_mailpieces(mps, true);
+        assert_eq!(ret.len(), 1 + names.len() - HOUSEHOLD_MAX);
+        assert_eq!(ret[0].name, "Jane Doe, John Roe and Mary Major");
+        assert_eq!(report.households.len(), 1);
+        assert_eq!(report.households[0].household.len(), HOUSEHOLD_MAX);
+        assert_eq!(report.removed_cnt(), HOUSEHOLD_MAX - 1);
+
+        // The rest keep their own mailpieces and titles.
+        let rest: Vec<&str> = ret[1..].iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(rest, names[HOUSEHOLD_MAX..].to_vec());
+        assert!(ret[1..].iter().all(|o| o.title1.is_some()));
+    }
+
+    #[test]
+    fn test_household_name() {
+        let cases = vec![
+            (vec![], ""),
+            (vec!["Jane Doe"], "Jane Doe"),
+            (vec!["Jane Doe", "John Roe"], "Jane Doe and John Roe"),
+            (
+                vec!["Jane Doe", "John Roe", "Mary Major"],
+                "Jane Doe, John Roe and Mary Major",
+            ),
+        ];
+
+        for (names, expected) in cases {
+            let names: Vec<String> = names.into_iter().map(String::from).collect();
+            assert_eq!(household_name(&names), expected);
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use crate::core::*;
+use crate::models::*;
+use crate::source::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+const DIR_SNAPSHOTS: &str = "snapshots";
+
+/// People of each roster keyed by source, such as `senate`.
+pub type Snapshot = BTreeMap<String, Vec<Person>>;
+
+/// Creates a snapshot of loaded rosters.
+pub fn snapshot(srcs: &[Box<dyn Source>]) -> Snapshot {
+    srcs.iter()
+        .map(|src| (src.key().to_string(), src.persons().to_vec()))
+        .collect()
+}
+
+/// Returns the snapshot file path for a mailing name, such as `2024-Q3`.
+fn snapshot_pth(name: &str) -> PathBuf {
+    PathBuf::from(DIR_SNAPSHOTS).join(format!("{name}.json"))
+}
+
+/// Writes a snapshot for a mailing name, replacing any earlier snapshot
+/// with the same name.
+pub fn save_snapshot(name: &str, snap: &Snapshot) -> Result<()> {
+    if !is_dry_run() {
+        fs::create_dir_all(DIR_SNAPSHOTS)?;
+    }
+    write_to_file(snap, &snapshot_pth(name).to_string_lossy())
+}
+
+/// Reads the snapshot for a mailing name.
+pub fn read_snapshot(name: &str) -> Result<Snapshot> {
+    read_from_file::<Snapshot>(&snapshot_pth(name).to_string_lossy())
+        .map_err(|err| anyhow!("unable to read snapshot {name}: {err}"))
+}
+
+/// Returns the latest snapshot name before a mailing name.
+pub fn prev_snapshot_name(name: &str) -> Result<Option<String>> {
+    if !PathBuf::from(DIR_SNAPSHOTS).exists() {
+        return Ok(None);
+    }
+    let mut ret: Option<String> = None;
+    for dir_ent in fs::read_dir(DIR_SNAPSHOTS)? {
+        let fle_nme = dir_ent?.file_name().to_string_lossy().to_string();
+        if let Some(snap_name) = fle_nme.strip_suffix(".json") {
+            if snap_name.contains('.') || snap_name >= name {
+                continue;
+            }
+            if ret.as_deref().is_none_or(|prev| snap_name > prev) {
+                ret = Some(snap_name.to_string());
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Writes a diff report as JSON next to the new snapshot.
+pub fn save_diff(diff: &Diff) -> Result<()> {
+    let pth = PathBuf::from(DIR_SNAPSHOTS).join(format!("{}.diff.json", diff.new));
+    write_to_file(diff, &pth.to_string_lossy())
+}
+
+/// Changes between two snapshots.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Diff {
+    pub old: String,
+    pub new: String,
+    pub rosters: Vec<RosterDiff>,
+}
+
+/// Changes to one roster.
+///
+/// A person is identified by name and URL.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct RosterDiff {
+    pub key: String,
+    pub added: Vec<Person>,
+    pub removed: Vec<Person>,
+    pub title_changes: Vec<TitleChange>,
+    pub adr_changes: Vec<AdrChange>,
+}
+
+/// A person whose titles changed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TitleChange {
+    pub name: String,
+    pub url: String,
+    pub old_title1: String,
+    pub old_title2: String,
+    pub title1: String,
+    pub title2: String,
+}
+
+/// A person whose addresses changed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AdrChange {
+    pub name: String,
+    pub url: String,
+    pub added: Vec<Address>,
+    pub removed: Vec<Address>,
+}
+
+impl Diff {
+    /// Compares two snapshots, roster by roster.
+    pub fn new(old_name: &str, old: &Snapshot, new_name: &str, new: &Snapshot) -> Diff {
+        let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let empty = Vec::new();
+        let rosters = keys
+            .into_iter()
+            .map(|key| {
+                let old_pers = old.get(key).unwrap_or(&empty);
+                let new_pers = new.get(key).unwrap_or(&empty);
+                diff_persons(key, old_pers, new_pers)
+            })
+            .collect();
+
+        Diff {
+            old: old_name.into(),
+            new: new_name.into(),
+            rosters,
+        }
+    }
+
+    /// Returns true when no roster changed.
+    pub fn is_empty(&self) -> bool {
+        self.rosters.iter().all(|roster| roster.is_empty())
+    }
+}
+
+impl RosterDiff {
+    /// Returns true when nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.title_changes.is_empty()
+            && self.adr_changes.is_empty()
+    }
+}
+
+/// Compares two lists of people from the same roster.
+pub fn diff_persons(key: &str, old: &[Person], new: &[Person]) -> RosterDiff {
+    let id = |per: &Person| (per.name.clone(), per.url.clone());
+    let old_map: BTreeMap<_, &Person> = old.iter().map(|per| (id(per), per)).collect();
+    let new_map: BTreeMap<_, &Person> = new.iter().map(|per| (id(per), per)).collect();
+
+    let mut ret = RosterDiff {
+        key: key.into(),
+        ..Default::default()
+    };
+    for (per_id, per) in &old_map {
+        if !new_map.contains_key(per_id) {
+            ret.removed.push((*per).clone());
+        }
+    }
+    for (per_id, new_per) in &new_map {
+        let Some(old_per) = old_map.get(per_id) else {
+            ret.added.push((*new_per).clone());
+            continue;
+        };
+
+        // Compare titles.
+        if old_per.title1 != new_per.title1 || old_per.title2 != new_per.title2 {
+            ret.title_changes.push(TitleChange {
+                name: new_per.name.clone(),
+                url: new_per.url.clone(),
+                old_title1: old_per.title1.clone(),
+                old_title2: old_per.title2.clone(),
+                title1: new_per.title1.clone(),
+                title2: new_per.title2.clone(),
+            });
+        }
+
+        // Compare addresses.
+        let old_adrs = old_per.adrs.as_deref().unwrap_or_default();
+        let new_adrs = new_per.adrs.as_deref().unwrap_or_default();
+        let added: Vec<Address> = new_adrs
+            .iter()
+            .filter(|adr| !old_adrs.contains(adr))
+            .cloned()
+            .collect();
+        let removed: Vec<Address> = old_adrs
+            .iter()
+            .filter(|adr| !new_adrs.contains(adr))
+            .cloned()
+            .collect();
+        if !added.is_empty() || !removed.is_empty() {
+            ret.adr_changes.push(AdrChange {
+                name: new_per.name.clone(),
+                url: new_per.url.clone(),
+                added,
+                removed,
+            });
+        }
+    }
+    ret
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "roster changes {} -> {}", self.old, self.new)?;
+        if self.is_empty() {
+            return write!(f, "\nno changes");
+        }
+        for roster in self.rosters.iter().filter(|roster| !roster.is_empty()) {
+            write!(f, "\n{roster}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RosterDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} added, {} removed, {} title changes, {} address changes",
+            self.key,
+            self.added.len(),
+            self.removed.len(),
+            self.title_changes.len(),
+            self.adr_changes.len()
+        )?;
+        for per in &self.added {
+            write!(f, "\n  + {}", per)?;
+        }
+        for per in &self.removed {
+            write!(f, "\n  - {}", per)?;
+        }
+        for chg in &self.title_changes {
+            write!(
+                f,
+                "\n  ~ {}: {}, {} -> {}, {}",
+                chg.name, chg.old_title1, chg.old_title2, chg.title1, chg.title2
+            )?;
+        }
+        for chg in &self.adr_changes {
+            write!(f, "\n  ~ {}:", chg.name)?;
+            for adr in &chg.added {
+                write!(f, "\n    + {}", adr)?;
+            }
+            for adr in &chg.removed {
+                write!(f, "\n    - {}", adr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use crate::prsr::*;
+
+    /// A senator with one address.
+    fn senator(name: &str, title1: &str, zip5: u32) -> Person {
+        Person {
+            title1: title1.into(),
+            url: format!("https://{}.senate.gov", name.to_lowercase()),
+            ..person(name, vec![address("1 MAIN ST", "ST", zip5)])
+        }
+    }
+
+    #[test]
+    fn test_diff_persons() {
+        let old = vec![
+            senator("Adams", "Senator", 10001),
+            senator("Baker", "Senator", 10002),
+            senator("Clark", "Senator", 10003),
+        ];
+        let mut moved = senator("Clark", "Senator", 10004);
+        let mut renamed_url = senator("Baker", "Senator", 10002);
+        renamed_url.url = "https://www.baker.senate.gov".into();
+        let new = vec![
+            senator("Adams", "Senate Majority Leader", 10001),
+            renamed_url.clone(),
+            moved.clone(),
+            senator("Davis", "Senator", 10005),
+        ];
+
+        let diff = diff_persons("senate", &old, &new);
+
+        // A changed URL is a different person.
+        let added: Vec<&str> = diff.added.iter().map(|per| per.name.as_str()).collect();
+        assert_eq!(added, vec!["Baker", "Davis"]);
+        assert_eq!(diff.removed, vec![senator("Baker", "Senator", 10002)]);
+        assert_eq!(diff.title_changes.len(), 1);
+        assert_eq!(diff.title_changes[0].old_title1, "Senator");
+        assert_eq!(diff.title_changes[0].title1, "Senate Majority Leader");
+        assert_eq!(diff.adr_changes.len(), 1);
+        assert_eq!(diff.adr_changes[0].added[0].zip5, 10004);
+        assert_eq!(diff.adr_changes[0].removed[0].zip5, 10003);
+    }
+
+    #[test]
+    fn test_diff_persons_set_name() {
+        // People named from parsed rosters match snapshots taken before
+        // name parsing.
+        let names = [
+            "J. \"Lou\" Correa",
+            "Dr. Jane A. Doe Ph.D.",
+            "Gov. Jared Polis",
+        ];
+        let old: Vec<Person> = names
+            .iter()
+            .map(|name| Person {
+                name: name_clean(name),
+                ..senator(name, "Senator", 10001)
+            })
+            .collect();
+        let new: Vec<Person> = names
+            .iter()
+            .map(|name| {
+                let mut per = senator(name, "Senator", 10001);
+                per.set_name(name);
+                per
+            })
+            .collect();
+
+        assert!(diff_persons("senate", &old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let mut old = Snapshot::new();
+        old.insert("senate".into(), vec![senator("Adams", "Senator", 10001)]);
+        old.insert(
+            "house".into(),
+            vec![senator("Evans", "Representative", 20001)],
+        );
+        let mut new = old.clone();
+        new.remove("house");
+
+        let diff = Diff::new("2024-Q3", &old, "2024-Q4", &new);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.rosters.len(), 2);
+        assert_eq!(diff.rosters[0].key, "house");
+        assert_eq!(diff.rosters[0].removed.len(), 1);
+        assert!(diff.rosters[1].is_empty());
+
+        assert!(Diff::new("2024-Q3", &old, "2024-Q4", &old).is_empty());
+    }
+}
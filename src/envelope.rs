@@ -1,20 +1,22 @@
 use crate::core::*;
+use crate::honorific::*;
+use crate::layout::*;
 use crate::mailing::*;
 use crate::models::*;
-use crate::prsr::*;
+use crate::rates::*;
+use anyhow::{anyhow, Result};
 use path::PaintMode;
 use printpdf::*;
 use serde::Deserialize;
 use serde::Serialize;
 
 const LYR_FROM: &str = "FROM";
-const WIDTH: Mm = Mm(241.3);
-const HEIGHT: Mm = Mm(104.8);
 
 static FNT_IMB: &[u8] = include_bytes!("../fonts/USPSIMBStandard.ttf");
 
 pub struct EnvelopeDocument {
     pub name: String,
+    pub spec: EnvelopeSpec,
     pub doc: PdfDocumentReference,
     pub font: IndirectFontRef,
     pub font_barcode: IndirectFontRef,
@@ -23,19 +25,11 @@ pub struct EnvelopeDocument {
 }
 
 impl EnvelopeDocument {
-    pub fn new(name: String) -> Self {
+    pub fn new(name: String, spec: EnvelopeSpec) -> Self {
         // Setup document.
-        // A Number 10 envelope, commonly used for business and personal correspondence,
-        // has dimensions of 241.3 mm in width, and 104.8 mm in height.
-        // Common envelope margins for printing can vary depending on the specific printer
-        // and the design requirements, but here are some general guidelines that are
-        // typically used:
-        //  * Top Margin: 10-15 mm
-        //  * Bottom Margin: 10-15 mm
-        //  * Left Margin: 10-15 mm
-        //  * Right Margin: 10-15 mm
-
-        let (doc, pg_idx1, lyr_idx1) = PdfDocument::new(&name, WIDTH, HEIGHT, LYR_FROM);
+        // One page per envelope, sized to the envelope.
+        let (width, height) = spec.size();
+        let (doc, pg_idx1, lyr_idx1) = PdfDocument::new(&name, Mm(width), Mm(height), LYR_FROM);
 
         // Setup fonts.
         let font = doc.add_builtin_font(BuiltinFont::Helvetica).unwrap();
@@ -43,6 +37,7 @@ impl EnvelopeDocument {
 
         Self {
             name,
+            spec,
             doc,
             font,
             font_barcode,
@@ -52,126 +47,418 @@ impl EnvelopeDocument {
     }
 
     /// Create one envelope per page.
-    pub fn create_page(&mut self, to: &Mailpiece, is_pg1: bool) {
+    ///
+    /// Window envelopes are not printed; the letter shows the addresses.
+    /// Fails when an address block does not fit the envelope.
+    pub fn create_page(&mut self, to: &Mailpiece, is_pg1: bool) -> Result<()> {
+        // Place each element before adding a page.
+        let from_lnes = from_lines();
+        let adr_lnes = adr_lines(to);
+        let ind_lnes = CFG.indicia.lines(CFG.mail_class);
+        let lyt = Layout::new(
+            self.spec,
+            &CFG.windows,
+            &from_lnes,
+            &adr_lnes,
+            ind_lnes.len(),
+            CFG.endorsement.text().map(|txt| (txt, CFG.endorsement_pos)),
+        )
+        .map_err(|err| anyhow!("unable to lay out envelope for {}: {}", to, err))?;
+
         // Create envelope page.
         let (pg_idx, lyr_idx) = if is_pg1 {
             (self.pg_idx1, self.lyr_idx1)
         } else {
-            self.doc.add_page(WIDTH, HEIGHT, LYR_FROM)
+            self.doc.add_page(Mm(lyt.width), Mm(lyt.height), LYR_FROM)
         };
 
         // Offset X for printer quirk.
         let offset = Mm(0.0);
 
-        // Get "FROM" layer.
-        let lyr_from = self.doc.get_page(pg_idx).get_layer(lyr_idx);
-
-        // Write "from" address on envelope.
-        // Return Address Placement:
-        // The return address (sender's address) should be placed in the
-        // upper left corner of the envelope within the area starting:
-        //  * 15 mm from the left edge of the envelope.
-        //  * 15 mm from the top edge of the envelope.
-        let margin_from = Mm(10.0);
-        lyr_from.begin_text_section();
-        lyr_from.set_font(&self.font, 10.0);
-        lyr_from.set_text_cursor(margin_from + offset, HEIGHT - margin_from);
-        lyr_from.set_line_height(12.0);
-        lyr_from.write_text(CFG.from.name.clone(), &self.font);
-        lyr_from.add_line_break();
-        lyr_from.write_text(CFG.from.address1.clone(), &self.font);
-        lyr_from.add_line_break();
-        lyr_from.write_text(
-            format!(
-                "{}  {}  {:05}-{:04}",
-                CFG.from.city, CFG.from.state, CFG.from.zip5, CFG.from.zip4
-            ),
-            &self.font,
-        );
-        lyr_from.end_text_section();
-
-        // Write "to" address on envelope.
-        // Address Block Placement:
-        // The address block (including the recipient's name, street address,
-        // city, state, and ZIP Code) should be placed within the area starting:
-        //  * 40 mm from the left edge of the envelope.
-        //  * 60 mm from the bottom edge of the envelope.
-        //  * 80 mm from the right edge of the envelope.
-        //  * 40 mm from the top edge of the envelope.
-        // Add layers for use in Adobe Illustrator.
-        let lyr_to = self.doc.get_page(pg_idx).add_layer("TO");
-        let margin_to_x = Mm(85.0) + offset;
-        let margin_to_y = Mm(45.0);
-        lyr_to.begin_text_section();
-        lyr_to.set_font(&self.font, 12.0);
-        lyr_to.set_text_cursor(margin_to_x, HEIGHT - margin_to_y);
-        lyr_to.set_line_height(18.0);
-        lyr_to.write_text(dot_remove(to.name.clone()).to_uppercase(), &self.font);
-        lyr_to.add_line_break();
-        if to.title1.is_some() {
-            lyr_to.write_text(to.title1.clone().unwrap(), &self.font);
-            lyr_to.add_line_break();
+        if !self.spec.is_window() {
+            // Get "FROM" layer.
+            let lyr_from = self.doc.get_page(pg_idx).get_layer(lyr_idx);
+
+            // Write "from" address in the upper left corner.
+            lyr_from.begin_text_section();
+            lyr_from.set_font(&self.font, lyt.from_font_size);
+            lyr_from.set_text_cursor(Mm(lyt.from.0) + offset, Mm(lyt.from.1));
+            lyr_from.set_line_height(lyt.from_lne_height);
+            for lne in from_lnes {
+                lyr_from.write_text(lne, &self.font);
+                lyr_from.add_line_break();
+            }
+            lyr_from.end_text_section();
+
+            // Write "to" address on envelope.
+            // The layout keeps the address block in the OCR read area and the
+            // barcode above the barcode clear zone.
+            // Add layers for use in Adobe Illustrator.
+            let lyr_to = self.doc.get_page(pg_idx).add_layer("TO");
+            lyr_to.begin_text_section();
+            lyr_to.set_font(&self.font, lyt.adr_font_size);
+            lyr_to.set_text_cursor(Mm(lyt.adr.0) + offset, Mm(lyt.adr.1));
+            lyr_to.set_line_height(lyt.adr_lne_height);
+            for lne in adr_lnes {
+                lyr_to.write_text(lne, &self.font);
+                lyr_to.add_line_break();
+            }
+            lyr_to.end_text_section();
+
+            // Write barcode.
+            // See USPS guidelines https://pe.usps.com/text/qsg300/Q201a.htm.
+            lyr_to.use_text(
+                to.barcode.clone(),
+                16.0,
+                Mm(lyt.barcode.llx) + offset,
+                Mm(lyt.barcode.lly),
+                &self.font_barcode,
+            );
         }
-        if to.title2.is_some() {
-            lyr_to.write_text(to.title2.clone().unwrap(), &self.font);
-            lyr_to.add_line_break();
+
+        // Write a permit indicia, or the mail class markings without a permit.
+        // See DMM 604.5 https://pe.usps.com/text/dmm300/604.htm.
+        let lyr_indicia = self.doc.get_page(pg_idx).add_layer("INDICIA");
+        lyr_indicia.begin_text_section();
+        lyr_indicia.set_font(&self.font, 8.0);
+        lyr_indicia.set_text_cursor(Mm(lyt.indicia.0), Mm(lyt.indicia.1));
+        lyr_indicia.set_line_height(INDICIA_LINE_HEIGHT);
+        for lne in ind_lnes {
+            lyr_indicia.write_text(lne, &self.font);
+            lyr_indicia.add_line_break();
+        }
+        lyr_indicia.end_text_section();
+        if CFG.indicia.fmt != IndiciaFmt::None {
+            // Draw rectangular outline around the indicia.
+            let area = lyt.indicia_box;
+            let rect = Rect::new(Mm(area.llx), Mm(area.lly), Mm(area.urx), Mm(area.ury))
+                .with_mode(PaintMode::Stroke);
+            lyr_indicia.add_rect(rect);
+        }
+
+        // Write the ancillary service endorsement.
+        // See DMM 507.1.5 https://pe.usps.com/text/dmm300/507.htm.
+        if let (Some(txt), Some((x, y))) = (CFG.endorsement.text(), lyt.endorsement) {
+            let lyr_end = self.doc.get_page(pg_idx).add_layer("ENDORSEMENT");
+            lyr_end.begin_text_section();
+            lyr_end.set_font(&self.font, 8.0);
+            lyr_end.set_text_cursor(Mm(x) + offset, Mm(y));
+            lyr_end.write_text(txt, &self.font);
+            lyr_end.end_text_section();
         }
-        lyr_to.write_text(to.address1.clone(), &self.font);
-        lyr_to.add_line_break();
-        lyr_to.write_text(
-            format!("{}  {}  {:05}-{:04}", to.city, to.state, to.zip5, to.zip4),
-            &self.font,
-        );
-        lyr_to.add_line_break();
-        // Write barcode.
-        // See USPS guidelines https://pe.usps.com/text/qsg300/Q201a.htm.
-        lyr_to.set_font(&self.font_barcode, 16.0);
-        lyr_to.write_text(to.barcode.clone(), &self.font_barcode);
-        lyr_to.end_text_section();
-
-        // // Write a permit indicia.
-        // let lyr_indicia = self.doc.get_page(pg_idx).add_layer("INDICIA");
-        // let margin_indicia_x = Mm(34.0);
-        // let margin_indicia_y = Mm(9.0);
-        // lyr_indicia.begin_text_section();
-        // lyr_indicia.set_font(&self.font, 8.0);
-        // lyr_indicia.set_text_cursor(WIDTH - margin_indicia_x, HEIGHT - margin_indicia_y);
-        // lyr_indicia.set_line_height(10.0);
-        // lyr_indicia.write_text("NONPROFIT", &self.font);
-        // lyr_indicia.add_line_break();
-        // lyr_indicia.write_text("PRSRT MKTG", &self.font);
-        // lyr_indicia.add_line_break();
-        // lyr_indicia.write_text("AUTO", &self.font);
-        // lyr_indicia.add_line_break();
-        // lyr_indicia.write_text("U.S. POSTAGE PAID", &self.font);
-        // lyr_indicia.add_line_break();
-        // lyr_indicia.write_text(CFG.indicia.city_state.clone(), &self.font);
-        // lyr_indicia.add_line_break();
-        // lyr_indicia.write_text(format!("PERMIT NO. {}", CFG.indicia.permit_id), &self.font);
-        // lyr_indicia.end_text_section();
-        // // Draw rectangular outline around the indicia.
-        // let ll_x = WIDTH - margin_indicia_x - Mm(2.0);
-        // let ll_y = HEIGHT - margin_indicia_y - Mm(20.0);
-        // let ur_x = WIDTH - Mm(5.0);
-        // let ur_y = HEIGHT - Mm(5.0);
-        // let rect = Rect::new(ll_x, ll_y, ur_x, ur_y).with_mode(PaintMode::Stroke);
-        // lyr_indicia.add_rect(rect);
-
-        // Write "Return Service Requested".
-        let lyr_rsr = self.doc.get_page(pg_idx).add_layer("RSR");
-        let margin_rsr_x = Mm(37.0);
-        let margin_rsr_y = Mm(30.0);
-        lyr_rsr.begin_text_section();
-        lyr_rsr.set_font(&self.font, 8.0);
-        lyr_rsr.set_text_cursor(WIDTH - margin_rsr_x, HEIGHT - margin_rsr_y);
-        lyr_rsr.write_text("Return Service Requested", &self.font);
-        lyr_rsr.end_text_section();
+
+        Ok(())
+    }
+}
+
+/// The return address lines.
+pub fn from_lines() -> Vec<String> {
+    vec![
+        CFG.from.name.clone(),
+        CFG.from.address1.clone(),
+        format!(
+            "{}  {}  {:05}-{:04}",
+            CFG.from.city, CFG.from.state, CFG.from.zip5, CFG.from.zip4
+        ),
+    ]
+}
+
+/// The address block lines, without the barcode.
+pub fn adr_lines(to: &Mailpiece) -> Vec<String> {
+    let mut ret = vec![FormOfAddress::new(to).envelope_name];
+    if let Some(title1) = &to.title1 {
+        ret.push(title1.clone());
     }
+    if let Some(title2) = &to.title2 {
+        ret.push(title2.clone());
+    }
+    ret.push(to.address1.clone());
+    ret.push(format!(
+        "{}  {}  {:05}-{:04}",
+        to.city, to.state, to.zip5, to.zip4
+    ));
+    ret
 }
 
 /// A permit indicia's unique information.
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Indicia {
+    /// The permit imprint format.
+    ///
+    /// Defaults to no indicia, which prints only the mail class markings.
+    #[serde(default)]
+    pub fmt: IndiciaFmt,
     pub city_state: String,
     pub permit_id: String,
+    /// The company name of a company permit imprint.
+    #[serde(default)]
+    pub company: String,
+}
+
+/// A permit imprint format.
+///
+/// See DMM 604.5.3 permit imprint formats.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IndiciaFmt {
+    /// No permit imprint; postage is paid another way.
+    #[default]
+    None,
+    /// USPS Marketing Mail Nonprofit permit imprint.
+    Nonprofit,
+    /// USPS Marketing Mail presorted permit imprint.
+    PresortedStandard,
+    /// First-Class Mail presorted permit imprint.
+    FirstClass,
+    /// A company permit imprint, which shows the company name in place of
+    /// the city, state and permit number.
+    CompanyPermit,
+}
+
+impl Indicia {
+    /// Checks the indicia against the mail class.
+    pub fn validate(&self, mail_class: MailClass) -> Result<()> {
+        let fmt_class = match self.fmt {
+            IndiciaFmt::None | IndiciaFmt::CompanyPermit => None,
+            IndiciaFmt::Nonprofit => Some(MailClass::Nonprofit),
+            IndiciaFmt::PresortedStandard => Some(MailClass::Marketing),
+            IndiciaFmt::FirstClass => Some(MailClass::FirstClass),
+        };
+        if fmt_class.is_some_and(|o| o != mail_class) {
+            return Err(anyhow!(
+                "{:?} indicia does not match {} mail class",
+                self.fmt,
+                mail_class
+            ));
+        }
+        match self.fmt {
+            IndiciaFmt::None => {}
+            IndiciaFmt::CompanyPermit if self.company.is_empty() => {
+                return Err(anyhow!("missing company name for company permit indicia"));
+            }
+            IndiciaFmt::CompanyPermit => {}
+            _ if self.city_state.is_empty() || self.permit_id.is_empty() => {
+                return Err(anyhow!("missing city, state or permit number for indicia"));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The indicia lines, starting with the mail class markings.
+    pub fn lines(&self, mail_class: MailClass) -> Vec<String> {
+        let mut ret: Vec<String> = mail_class
+            .markings()
+            .iter()
+            .map(|o| o.to_string())
+            .collect();
+        match self.fmt {
+            IndiciaFmt::None => {}
+            IndiciaFmt::CompanyPermit => {
+                ret.push("U.S. POSTAGE PAID".into());
+                ret.push(self.company.to_uppercase());
+            }
+            _ => {
+                ret.push("U.S. POSTAGE PAID".into());
+                ret.push(self.city_state.to_uppercase());
+                ret.push(format!("PERMIT NO. {}", self.permit_id));
+            }
+        }
+        ret
+    }
+}
+
+/// An ancillary service endorsement.
+///
+/// Sets the printed endorsement and the barcode STID together.
+/// See DMM 507.1.5 ancillary service endorsements.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Endorsement {
+    /// No endorsement; undeliverable pieces are discarded.
+    None,
+    AddressServiceRequested,
+    ChangeServiceRequested,
+    ForwardingServiceRequested,
+    #[default]
+    ReturnServiceRequested,
+}
+
+impl Endorsement {
+    /// The printed endorsement, or none.
+    pub fn text(&self) -> Option<&'static str> {
+        match self {
+            Endorsement::None => None,
+            Endorsement::AddressServiceRequested => Some("Address Service Requested"),
+            Endorsement::ChangeServiceRequested => Some("Change Service Requested"),
+            Endorsement::ForwardingServiceRequested => Some("Forwarding Service Requested"),
+            Endorsement::ReturnServiceRequested => Some("Return Service Requested"),
+        }
+    }
+
+    /// The STID for mailpiece barcodes.
+    ///
+    /// Printed endorsements request manual address corrections;
+    /// no endorsement requests none. Fails for Change Service Requested,
+    /// which requires ACS (DMM 507.1.5).
+    pub fn stid(&self, mail_class: MailClass) -> Result<&'static str> {
+        match (self, mail_class) {
+            (Endorsement::ChangeServiceRequested, _) => Err(anyhow!(
+                "Change Service Requested requires ACS; use another endorsement"
+            )),
+            (Endorsement::None, MailClass::FirstClass) => Ok(STID_FCM_NO_ADR),
+            (Endorsement::None, _) => Ok(STID_NO_ADR),
+            (_, MailClass::FirstClass) => Ok(STID_FCM_RSR),
+            (_, _) => Ok(STID_RSR),
+        }
+    }
+}
+
+/// Where the endorsement is printed.
+///
+/// See DMM 507.1.5.3 endorsement placement.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EndorsementPos {
+    /// Directly below the return address.
+    BelowReturnAdr,
+    /// Directly above the address block.
+    AboveAdr,
+    /// Directly to the left of the postage area.
+    LeftOfPostage,
+    /// Directly below the postage area.
+    #[default]
+    BelowPostage,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indicia(fmt: IndiciaFmt) -> Indicia {
+        Indicia {
+            fmt,
+            city_state: "Boulder CO".into(),
+            permit_id: "123".into(),
+            company: "Acme Inc".into(),
+        }
+    }
+
+    #[test]
+    fn test_indicia_lines() {
+        let cases = vec![
+            (
+                IndiciaFmt::None,
+                MailClass::Marketing,
+                vec!["PRSRT MKTG", "AUTO"],
+            ),
+            (
+                IndiciaFmt::Nonprofit,
+                MailClass::Nonprofit,
+                vec![
+                    "NONPROFIT",
+                    "PRSRT MKTG",
+                    "AUTO",
+                    "U.S. POSTAGE PAID",
+                    "BOULDER CO",
+                    "PERMIT NO. 123",
+                ],
+            ),
+            (
+                IndiciaFmt::CompanyPermit,
+                MailClass::FirstClass,
+                vec!["PRSRT FIRST-CLASS", "AUTO", "U.S. POSTAGE PAID", "ACME INC"],
+            ),
+        ];
+
+        for (fmt, mail_class, expected) in cases {
+            assert_eq!(indicia(fmt).lines(mail_class), expected);
+        }
+    }
+
+    #[test]
+    fn test_indicia_validate() {
+        let cases = vec![
+            (IndiciaFmt::None, MailClass::FirstClass, true),
+            (IndiciaFmt::Nonprofit, MailClass::Nonprofit, true),
+            (IndiciaFmt::Nonprofit, MailClass::Marketing, false),
+            (IndiciaFmt::PresortedStandard, MailClass::Marketing, true),
+            (IndiciaFmt::FirstClass, MailClass::Nonprofit, false),
+            (IndiciaFmt::CompanyPermit, MailClass::FirstClass, true),
+        ];
+
+        for (fmt, mail_class, expected) in cases {
+            assert_eq!(
+                indicia(fmt).validate(mail_class).is_ok(),
+                expected,
+                "{fmt:?}"
+            );
+        }
+
+        // Permit formats need a permit number.
+        let mut ind = indicia(IndiciaFmt::FirstClass);
+        ind.permit_id = "".into();
+        assert!(ind.validate(MailClass::FirstClass).is_err());
+        let mut ind = indicia(IndiciaFmt::CompanyPermit);
+        ind.company = "".into();
+        assert!(ind.validate(MailClass::FirstClass).is_err());
+    }
+
+    #[test]
+    fn test_endorsement_stid() {
+        let cases = vec![
+            (
+                Endorsement::None,
+                MailClass::Nonprofit,
+                Some(STID_NO_ADR),
+                None,
+            ),
+            (
+                Endorsement::None,
+                MailClass::FirstClass,
+                Some(STID_FCM_NO_ADR),
+                None,
+            ),
+            (
+                Endorsement::ReturnServiceRequested,
+                MailClass::Nonprofit,
+                Some(STID_RSR),
+                Some("Return Service Requested"),
+            ),
+            (
+                Endorsement::AddressServiceRequested,
+                MailClass::Marketing,
+                Some(STID_RSR),
+                Some("Address Service Requested"),
+            ),
+            (
+                Endorsement::ForwardingServiceRequested,
+                MailClass::FirstClass,
+                Some(STID_FCM_RSR),
+                Some("Forwarding Service Requested"),
+            ),
+            // Change Service Requested requires ACS.
+            (
+                Endorsement::ChangeServiceRequested,
+                MailClass::FirstClass,
+                None,
+                Some("Change Service Requested"),
+            ),
+            (
+                Endorsement::ChangeServiceRequested,
+                MailClass::Nonprofit,
+                None,
+                Some("Change Service Requested"),
+            ),
+        ];
+
+        for (end, mail_class, expected_stid, expected_txt) in cases {
+            assert_eq!(
+                end.stid(mail_class).ok(),
+                expected_stid,
+                "{end:?} {mail_class}"
+            );
+            assert_eq!(end.text(), expected_txt);
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use crate::core::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::source::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
 use reqwest::Client;
@@ -12,6 +13,9 @@ use std::ops::Add;
 use std::path::Path;
 
 const FLE_PTH: &str = "executive.json";
+
+/// The source key.
+pub const KEY_EXECUTIVE: &str = "executive";
 const FLE_PTH_URL: &str = "executive.url.json";
 
 const CAP_PER: usize = 4;
@@ -32,28 +36,6 @@ impl Executive {
         }
     }
 
-    pub async fn load() -> Result<Executive> {
-        // Read file from disk.
-        let mut exec = match read_from_file::<Executive>(FLE_PTH) {
-            Ok(exec_from_disk) => exec_from_disk,
-            Err(err) => {
-                let mut exec = Executive::new();
-
-                // Set members.
-                exec.persons = exec.set_members();
-
-                // Write file to disk.
-                write_to_file(&exec, FLE_PTH)?;
-
-                exec
-            }
-        };
-
-        println!("{} executive branch members", exec.persons.len());
-
-        Ok(exec)
-    }
-
     pub fn set_members(&self) -> Vec<Person> {
         let mut ret = Vec::new();
 
@@ -117,3 +99,37 @@ impl Executive {
         ret
     }
 }
+
+impl Source for Executive {
+    fn key(&self) -> &'static str {
+        KEY_EXECUTIVE
+    }
+
+    fn file_path(&self) -> &'static str {
+        FLE_PTH
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn role(&self) -> Role {
+        self.role.clone()
+    }
+
+    fn persons(&self) -> &[Person] {
+        &self.persons
+    }
+
+    fn persons_mut(&mut self) -> &mut Vec<Person> {
+        &mut self.persons
+    }
+
+    /// Set members.
+    fn fetch_members(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(async move {
+            self.persons = self.set_members();
+            Ok(())
+        })
+    }
+}
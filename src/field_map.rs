@@ -0,0 +1,290 @@
+use crate::core::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// The field map format version read by this build.
+pub const FIELD_MAP_FORMAT: u32 = 1;
+
+/// The built-in PS Form 3602-N field map.
+///
+/// Fills page 1 only. The page 2 Part A price lines need positions measured
+/// from the printed form; their values are `prc_*`, `*_cnt`,
+/// `postage_subtotal_*` and `part_a_subtotal`. Sets no `acro_name`, so text
+/// is written at each position.
+static FIELD_MAP_PS3602N: &str = include_str!("../forms/ps3602n.json");
+
+/// Built-in field maps, one per form.
+///
+/// The PS Form 3602-R and 3600-FCM maps place the page 1 text fields at the
+/// PS Form 3602-N positions; check them against the printed form.
+static FIELD_MAPS_BUILTIN: &[&str] = &[
+    FIELD_MAP_PS3602N,
+    include_str!("../forms/ps3602r.json"),
+    include_str!("../forms/ps3600fcm.json"),
+];
+
+/// Where and how to write each value on a form.
+///
+/// Read from JSON so a revised USPS form needs only a new map.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldMap {
+    /// The field map format version, `FIELD_MAP_FORMAT`.
+    pub format: u32,
+    /// The form name, such as `PS Form 3602-N`.
+    pub form: String,
+    /// The form edition, such as `July 2024`.
+    pub version: String,
+    /// Where to fetch the blank form.
+    pub url: String,
+    pub fields: Vec<FieldSpec>,
+}
+
+/// A form field.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldSpec {
+    /// The field key, such as `total_pieces`, used in messages.
+    pub name: String,
+    /// The AcroForm field name, filled instead of writing text at the
+    /// position when the form has the field.
+    #[serde(default)]
+    pub acro_name: Option<String>,
+    /// Page number, starting at 1.
+    pub page: usize,
+    /// Text position in points from the lower left corner of the page.
+    pub x: f32,
+    pub y: f32,
+    pub font_size: f32,
+    /// The value key, such as `mailpiece_cnt`.
+    pub value: String,
+    #[serde(default)]
+    pub fmt: Fmt,
+    /// Skips the field when this value key is zero.
+    #[serde(default)]
+    pub unless_zero: Option<String>,
+}
+
+/// How a value is written.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Fmt {
+    /// Text as is.
+    #[default]
+    Text,
+    /// A count with thousands separators, such as `1,234`.
+    Cnt,
+    /// A 3-digit sequence number, such as `007`.
+    Seq,
+    /// A piece price in dollars, such as `0.173`.
+    Price,
+    /// An amount in dollars, such as `25.95`.
+    Money,
+    /// `X` when true.
+    Check,
+}
+
+/// A value for a form field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    Cnt(usize),
+    Amt(f64),
+    Flag(bool),
+}
+
+/// Field values by value key.
+pub type FieldValues = BTreeMap<String, FieldValue>;
+
+impl FieldValue {
+    /// Returns true for a zero count or amount, or a false flag.
+    pub fn is_zero(&self) -> bool {
+        match self {
+            FieldValue::Text(txt) => txt.is_empty(),
+            FieldValue::Cnt(cnt) => *cnt == 0,
+            FieldValue::Amt(amt) => *amt == 0.0,
+            FieldValue::Flag(flag) => !flag,
+        }
+    }
+}
+
+impl Fmt {
+    /// Formats a value.
+    ///
+    /// Fails when the formatter does not apply to the value.
+    pub fn apply(&self, val: &FieldValue) -> Result<String> {
+        match (self, val) {
+            (Fmt::Text, FieldValue::Text(txt)) => Ok(txt.clone()),
+            (Fmt::Text, FieldValue::Cnt(cnt)) => Ok(cnt.to_string()),
+            (Fmt::Cnt, FieldValue::Cnt(cnt)) => Ok(numfmt(*cnt)),
+            (Fmt::Seq, FieldValue::Cnt(cnt)) => Ok(format!("{:03}", cnt)),
+            (Fmt::Price, FieldValue::Amt(amt)) => Ok(format!("{:.3}", amt)),
+            (Fmt::Money, FieldValue::Amt(amt)) => Ok(format!("{:.2}", amt)),
+            (Fmt::Check, FieldValue::Flag(flag)) => Ok(if *flag { "X" } else { "" }.into()),
+            _ => Err(anyhow!("unable to format {:?} as {:?}", val, self)),
+        }
+    }
+}
+
+impl FieldMap {
+    /// Parses a field map from JSON.
+    pub fn parse(txt: &str) -> Result<FieldMap> {
+        let ret: FieldMap = serde_json::from_str(txt)?;
+        if ret.format != FIELD_MAP_FORMAT {
+            return Err(anyhow!(
+                "unsupported field map format {} for {}; expected {}",
+                ret.format,
+                ret.form,
+                FIELD_MAP_FORMAT
+            ));
+        }
+        Ok(ret)
+    }
+
+    /// Reads a field map from disk.
+    ///
+    /// An empty path is the built-in map for a form, such as `PS Form 3602-N`.
+    pub fn load(pth: &str, form: &str) -> Result<FieldMap> {
+        if pth.is_empty() {
+            for txt in FIELD_MAPS_BUILTIN {
+                let map = FieldMap::parse(txt)?;
+                if map.form == form {
+                    return Ok(map);
+                }
+            }
+            return Err(anyhow!("no built-in {} field map", form));
+        }
+        let txt = fs::read_to_string(pth)
+            .map_err(|err| anyhow!("unable to read field map {}: {}", pth, err))?;
+        FieldMap::parse(&txt).map_err(|err| anyhow!("{}: {}", pth, err))
+    }
+
+    /// Formats the text of each field.
+    ///
+    /// Skips fields with empty text or a zero `unless_zero` value.
+    /// Fails on an unknown value key.
+    pub fn fill(&self, vals: &FieldValues) -> Result<Vec<(&FieldSpec, String)>> {
+        let get = |key: &str| {
+            vals.get(key).ok_or(anyhow!(
+                "unknown value {:?} in {} field map",
+                key,
+                self.form
+            ))
+        };
+
+        let mut ret = Vec::with_capacity(self.fields.len());
+        for spec in self.fields.iter() {
+            let val = get(&spec.value)?;
+            if let Some(key) = &spec.unless_zero {
+                if get(key)?.is_zero() {
+                    continue;
+                }
+            }
+            let txt = spec
+                .fmt
+                .apply(val)
+                .map_err(|err| anyhow!("field {}: {}", spec.name, err))?;
+            if !txt.is_empty() {
+                ret.push((spec, txt));
+            }
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(value: &str, fmt: Fmt, unless_zero: Option<&str>) -> FieldSpec {
+        FieldSpec {
+            name: value.into(),
+            acro_name: None,
+            page: 1,
+            x: 0.0,
+            y: 0.0,
+            font_size: 9.0,
+            value: value.into(),
+            fmt,
+            unless_zero: unless_zero.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_fmt_apply() {
+        let cases = vec![
+            (Fmt::Text, FieldValue::Text("CRID".into()), "CRID"),
+            (Fmt::Cnt, FieldValue::Cnt(1234), "1,234"),
+            (Fmt::Seq, FieldValue::Cnt(7), "007"),
+            (Fmt::Price, FieldValue::Amt(0.173), "0.173"),
+            (Fmt::Money, FieldValue::Amt(25.9501), "25.95"),
+            (Fmt::Check, FieldValue::Flag(true), "X"),
+            (Fmt::Check, FieldValue::Flag(false), ""),
+        ];
+
+        for (fmt, val, expected) in cases {
+            assert_eq!(fmt.apply(&val).unwrap(), expected);
+        }
+        assert!(Fmt::Money.apply(&FieldValue::Text("1".into())).is_err());
+    }
+
+    #[test]
+    fn test_fill() {
+        let map = FieldMap {
+            format: FIELD_MAP_FORMAT,
+            form: "PS Form 3602-N".into(),
+            version: "July 2024".into(),
+            url: "".into(),
+            fields: vec![
+                spec("crid", Fmt::Text, None),
+                spec("five_dig_cnt", Fmt::Cnt, Some("five_dig_cnt")),
+                spec("aadc_cnt", Fmt::Cnt, Some("aadc_cnt")),
+                spec("email", Fmt::Text, None),
+            ],
+        };
+        let mut vals = FieldValues::new();
+        vals.insert("crid".into(), FieldValue::Text("1234".into()));
+        vals.insert("five_dig_cnt".into(), FieldValue::Cnt(150));
+        vals.insert("aadc_cnt".into(), FieldValue::Cnt(0));
+        vals.insert("email".into(), FieldValue::Text("".into()));
+
+        let txts: Vec<(&str, String)> = map
+            .fill(&vals)
+            .unwrap()
+            .into_iter()
+            .map(|(spec, txt)| (spec.name.as_str(), txt))
+            .collect();
+        assert_eq!(
+            txts,
+            vec![("crid", "1234".into()), ("five_dig_cnt", "150".into())]
+        );
+
+        // Unknown value keys fail.
+        vals.remove("crid");
+        assert!(map.fill(&vals).is_err());
+    }
+
+    #[test]
+    fn test_parse_format() {
+        for form in ["PS Form 3602-N", "PS Form 3602-R", "PS Form 3600-FCM"] {
+            let map = FieldMap::load("", form).unwrap();
+            assert_eq!(map.form, form);
+            assert!(!map.fields.is_empty());
+            assert!(map.fields.iter().all(|o| o.acro_name.is_none()));
+        }
+        assert!(FieldMap::load("", "PS Form 3541").is_err());
+
+        // A field may name its AcroForm field.
+        let txt = FIELD_MAP_PS3602N.replacen(
+            "\"name\": \"crid\",",
+            "\"name\": \"crid\", \"acro_name\": \"CRID\",",
+            1,
+        );
+        let map = FieldMap::parse(&txt).unwrap();
+        let crid = map.fields.iter().find(|o| o.name == "crid").unwrap();
+        assert_eq!(crid.acro_name.as_deref(), Some("CRID"));
+
+        let txt = FIELD_MAP_PS3602N.replacen("\"format\": 1", "\"format\": 99", 1);
+        assert!(FieldMap::parse(&txt).is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use crate::models::*;
+use chrono::NaiveDate;
+
+/// A calendar date.
+pub fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+/// The return address lines of a mailer.
+pub fn from_lnes() -> Vec<String> {
+    vec![
+        "Acme Civic League".into(),
+        "PO Box 100".into(),
+        "BOULDER  CO  80306-0100".into(),
+    ]
+}
+
+/// An address in a placeholder city.
+pub fn address(address1: &str, state: &str, zip5: u32) -> Address {
+    Address {
+        address1: address1.into(),
+        city: "CITY".into(),
+        state: state.into(),
+        zip5,
+        ..Default::default()
+    }
+}
+
+/// A person with addresses.
+pub fn person(name: &str, adrs: Vec<Address>) -> Person {
+    Person {
+        name: name.into(),
+        adrs: Some(adrs),
+        ..Default::default()
+    }
+}
+
+/// A mailpiece to an office on Capitol Hill.
+pub fn mailpiece(name: &str) -> Mailpiece {
+    Mailpiece {
+        name: name.into(),
+        address1: "100 CONSTITUTION AVE NW".into(),
+        city: "WASHINGTON".into(),
+        state: "DC".into(),
+        zip5: 20510,
+        zip4: 1,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,312 @@
+use crate::executive::*;
+use crate::house::*;
+use crate::models::*;
+use crate::prsr::*;
+use crate::senate::*;
+use crate::state::*;
+use heck::ToTitleCase;
+
+/// Military ranks: the name prefix, the full rank, and the rank used in
+/// salutations.
+///
+/// Prefixes match `NAME_PREFIXES`.
+const RANKS: [(&str, &str, &str); 22] = [
+    ("Lt. Gen.", "Lieutenant General", "General"),
+    ("Maj. Gen.", "Major General", "General"),
+    ("Brig. Gen.", "Brigadier General", "General"),
+    ("Vice Adm.", "Vice Admiral", "Admiral"),
+    ("Rear Adm.", "Rear Admiral", "Admiral"),
+    ("Lt. Col.", "Lieutenant Colonel", "Colonel"),
+    ("Lieutenant General", "Lieutenant General", "General"),
+    ("Major General", "Major General", "General"),
+    ("Brigadier General", "Brigadier General", "General"),
+    ("Vice Admiral", "Vice Admiral", "Admiral"),
+    ("Rear Admiral", "Rear Admiral", "Admiral"),
+    ("LTG", "Lieutenant General", "General"),
+    ("VADM", "Vice Admiral", "Admiral"),
+    ("RADM", "Rear Admiral", "Admiral"),
+    ("Gen.", "General", "General"),
+    ("General", "General", "General"),
+    ("Adm.", "Admiral", "Admiral"),
+    ("Admiral", "Admiral", "Admiral"),
+    ("Col.", "Colonel", "Colonel"),
+    ("Capt.", "Captain", "Captain"),
+    ("Cmdr.", "Commander", "Commander"),
+    ("FLTCM.", "Fleet Master Chief", "Master Chief"),
+];
+
+/// How a recipient is addressed on envelopes and letters.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FormOfAddress {
+    /// The envelope name line, such as `THE HONORABLE JANE SMITH`.
+    pub envelope_name: String,
+    /// The letter inside address lines.
+    pub inside_address: Vec<String>,
+    /// The letter salutation, such as `Dear Senator Smith:`.
+    pub salutation: String,
+}
+
+impl FormOfAddress {
+    /// Chooses the form of address from the recipient's source, title and role.
+    pub fn new(mp: &Mailpiece) -> FormOfAddress {
+        let title1 = mp.title1.as_deref().unwrap_or_default();
+        let nme = if mp.name_parts.is_empty() {
+            name_parse(&mp.name)
+        } else {
+            mp.name_parts.clone()
+        };
+        let rank = match mp.role {
+            Some(Role::Military) => RANKS
+                .iter()
+                .find(|(prefix, _, _)| prefix.eq_ignore_ascii_case(&nme.prefix))
+                .map(|(_, rank, rank_sal)| (*rank, *rank_sal)),
+            _ => None,
+        };
+        let name = nme.formal();
+        let family = &nme.family;
+
+        // Find the name line, the salutation and an office line that
+        // replaces the titles.
+        let honorable = format!("The Honorable {}", name);
+        let (name_lne, salutation, office_lne) = match mp.src.as_str() {
+            KEY_SENATE => (
+                honorable,
+                format!("Senator {}", family),
+                Some("United States Senate".to_string()),
+            ),
+            KEY_HOUSE => (
+                honorable,
+                format!("Representative {}", family),
+                Some("United States House of Representatives".into()),
+            ),
+            KEY_STATE => (
+                honorable,
+                format!("Governor {}", family),
+                Some("Office of the Governor".into()),
+            ),
+            KEY_EXECUTIVE if title1 == "Office of the President" => (
+                "The President".into(),
+                format!("President {}", family),
+                Some("The White House".into()),
+            ),
+            KEY_EXECUTIVE if title1 == "Office of the Vice President" => (
+                "The Vice President".into(),
+                format!("Vice President {}", family),
+                None,
+            ),
+            KEY_EXECUTIVE if title1.starts_with("Department of ") => (
+                honorable,
+                format!("Secretary {}", family),
+                Some(title1.replacen("Department", "Secretary", 1)),
+            ),
+            _ if is_administrator(title1) => (honorable, format!("Administrator {}", family), None),
+            _ => match rank {
+                Some((rank, rank_sal)) => (
+                    format!("{} {}", rank, name),
+                    format!("{} {}", rank_sal, family),
+                    None,
+                ),
+                None if nme.prefix == "Dr." => {
+                    (format!("Dr. {}", name), format!("Dr. {}", family), None)
+                }
+                None => (name.clone(), name, None),
+            },
+        };
+
+        // Build the inside address.
+        let mut inside_address = vec![name_lne.clone()];
+        match office_lne {
+            Some(office_lne) => inside_address.push(office_lne),
+            None => inside_address.extend(
+                [&mp.title1, &mp.title2]
+                    .into_iter()
+                    .flatten()
+                    .filter(|o| !o.is_empty())
+                    .cloned(),
+            ),
+        }
+        inside_address.push(mp.address1.clone());
+        let zip = match mp.zip4 {
+            0 => format!("{:05}", mp.zip5),
+            _ => format!("{:05}-{:04}", mp.zip5, mp.zip4),
+        };
+        inside_address.push(format!("{}, {} {}", mp.city.to_title_case(), mp.state, zip));
+
+        FormOfAddress {
+            envelope_name: dot_remove(name_lne).to_uppercase(),
+            inside_address,
+            salutation: format!("Dear {}:", salutation),
+        }
+    }
+}
+
+/// Whether a title is the head of an agency, such as "NASA Administrator".
+fn is_administrator(title: &str) -> bool {
+    let title = title.trim();
+    title.eq_ignore_ascii_case("Administrator")
+        || (title.ends_with(" Administrator")
+            && !["Deputy", "Associate", "Assistant"]
+                .iter()
+                .any(|o| title.contains(o)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use crate::military::KEY_MILITARY;
+    use crate::nasa::KEY_NASA;
+    use crate::observer::KEY_OBSERVER;
+
+    #[test]
+    fn test_form_of_address() {
+        let cases = vec![
+            (
+                ("Chris Van Hollen", KEY_SENATE, "", Role::Political),
+                "THE HONORABLE CHRIS VAN HOLLEN",
+                "The Honorable Chris Van Hollen",
+                "United States Senate",
+                "Dear Senator Van Hollen:",
+            ),
+            (
+                ("J. \"Lou\" Correa", KEY_HOUSE, "", Role::Political),
+                "THE HONORABLE LOU CORREA",
+                "The Honorable Lou Correa",
+                "United States House of Representatives",
+                "Dear Representative Correa:",
+            ),
+            (
+                ("Jared Polis", KEY_STATE, "", Role::Political),
+                "THE HONORABLE JARED POLIS",
+                "The Honorable Jared Polis",
+                "Office of the Governor",
+                "Dear Governor Polis:",
+            ),
+            (
+                (
+                    "Joe Biden",
+                    KEY_EXECUTIVE,
+                    "Office of the President",
+                    Role::Political,
+                ),
+                "THE PRESIDENT",
+                "The President",
+                "The White House",
+                "Dear President Biden:",
+            ),
+            (
+                (
+                    "Kamala Harris",
+                    KEY_EXECUTIVE,
+                    "Office of the Vice President",
+                    Role::Political,
+                ),
+                "THE VICE PRESIDENT",
+                "The Vice President",
+                "Office of the Vice President",
+                "Dear Vice President Harris:",
+            ),
+            (
+                (
+                    "Antony Blinken",
+                    KEY_EXECUTIVE,
+                    "Department of State",
+                    Role::Political,
+                ),
+                "THE HONORABLE ANTONY BLINKEN",
+                "The Honorable Antony Blinken",
+                "Secretary of State",
+                "Dear Secretary Blinken:",
+            ),
+            (
+                (
+                    "Gen. Charles Q. Brown Jr.",
+                    KEY_MILITARY,
+                    "CHAIRMAN OF THE JOINT CHIEFS OF STAFF",
+                    Role::Military,
+                ),
+                "GENERAL CHARLES Q BROWN JR",
+                "General Charles Q. Brown Jr.",
+                "CHAIRMAN OF THE JOINT CHIEFS OF STAFF",
+                "Dear General Brown:",
+            ),
+            (
+                ("Vice Adm. Jane Doe", KEY_MILITARY, "", Role::Military),
+                "VICE ADMIRAL JANE DOE",
+                "Vice Admiral Jane Doe",
+                "100 CONSTITUTION AVE NW",
+                "Dear Admiral Doe:",
+            ),
+            (
+                ("FLTCM. John Smith", KEY_MILITARY, "", Role::Military),
+                "FLEET MASTER CHIEF JOHN SMITH",
+                "Fleet Master Chief John Smith",
+                "100 CONSTITUTION AVE NW",
+                "Dear Master Chief Smith:",
+            ),
+            (
+                (
+                    "Bill Nelson",
+                    KEY_NASA,
+                    "NASA Administrator",
+                    Role::Scientific,
+                ),
+                "THE HONORABLE BILL NELSON",
+                "The Honorable Bill Nelson",
+                "NASA Administrator",
+                "Dear Administrator Nelson:",
+            ),
+            (
+                (
+                    "Pam Melroy",
+                    KEY_NASA,
+                    "NASA Deputy Administrator",
+                    Role::Scientific,
+                ),
+                "PAM MELROY",
+                "Pam Melroy",
+                "NASA Deputy Administrator",
+                "Dear Pam Melroy:",
+            ),
+            (
+                (
+                    "Dr. Jane Doe, PhD",
+                    KEY_NASA,
+                    "Chief Scientist",
+                    Role::Scientific,
+                ),
+                "DR JANE DOE",
+                "Dr. Jane Doe",
+                "Chief Scientist",
+                "Dear Dr. Doe:",
+            ),
+            (
+                ("Jane Q. Public", KEY_OBSERVER, "", Role::Observer),
+                "JANE Q PUBLIC",
+                "Jane Q. Public",
+                "100 CONSTITUTION AVE NW",
+                "Dear Jane Q. Public:",
+            ),
+        ];
+
+        for ((name, src, title1, role), envelope_name, inside_lne1, inside_lne2, salutation) in
+            cases
+        {
+            let mp = Mailpiece {
+                title1: Some(title1.into()).filter(|o: &String| !o.is_empty()),
+                role: Some(role),
+                src: src.into(),
+                ..mailpiece(name)
+            };
+            let frm = FormOfAddress::new(&mp);
+            assert_eq!(frm.envelope_name, envelope_name, "{}", mp.name);
+            assert_eq!(frm.inside_address[0], inside_lne1, "{}", mp.name);
+            assert_eq!(frm.inside_address[1], inside_lne2, "{}", mp.name);
+            assert_eq!(
+                frm.inside_address.last().unwrap(),
+                "Washington, DC 20510-0001"
+            );
+            assert_eq!(frm.salutation, salutation, "{}", mp.name);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::core::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::source::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
 use reqwest::Client;
@@ -13,6 +14,9 @@ use std::path::Path;
 
 const FLE_PTH: &str = "house.json";
 
+/// The source key.
+pub const KEY_HOUSE: &str = "house";
+
 /// The total number of members in the U.S. House of Representatives is 441. This includes 435 voting members who represent the 50 states and 6 non-voting members who represent the District of Columbia, Puerto Rico, and four other U.S. territories (American Samoa, Guam, the Northern Mariana Islands, and the U.S. Virgin Islands). Some members may be vacant.
 const CAP_PER: usize = 441;
 
@@ -32,37 +36,10 @@ impl House {
         }
     }
 
-    pub async fn load() -> Result<House> {
-        // Read file from disk.
-        let mut house = match read_from_file::<House>(FLE_PTH) {
-            Ok(mut house_from_disk) => house_from_disk,
-
-            Err(err) => {
-                eprintln!("err: read file: {err}");
-                let mut house = House::new();
-
-                // Fetch members.
-                house.persons = house.fetch_members().await?;
-
-                // Write file to disk.
-                write_to_file(&house, FLE_PTH)?;
-
-                house
-            }
-        };
-
-        println!("{} representatives", house.persons.len());
-
-        // Fetch addresses.
-        house.fetch_adrs().await?;
-
-        Ok(house)
-    }
-
     /// Fetch members from network.
-    pub async fn fetch_members(&self) -> Result<Vec<Person>> {
+    pub async fn fetch_members_list(&self) -> Result<Vec<Person>> {
         let url = "https://www.house.gov/representatives";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_HOUSE).await?;
         let document = Html::parse_document(&html);
         let mut pers = Vec::with_capacity(CAP_PER);
 
@@ -83,37 +60,13 @@ impl House {
                 {
                     let full_name = format!("{} {}", name_fst.trim(), name_lst.trim());
                     // eprintln!("{}", full_name);
-                    per.name = name_clean(&full_name);
+                    per.set_name(&full_name);
                 }
                 // Skip empty or vacancy.
                 // "Mike - Vacancy"
                 if per.name.is_empty() || per.name.contains("Vacancy") {
                     continue;
                 }
-                // Edit some names.
-                match per.name.as_str() {
-                    "J. Correa" => {
-                        per.name = "Lou Correa".into();
-                    }
-                    "A. Ferguson" => {
-                        per.name = "Drew Ferguson".into();
-                    }
-                    "H. Griffith" => {
-                        per.name = "Morgan Griffith".into();
-                    }
-                    "J. Hill" => {
-                        per.name = "French Hill".into();
-                    }
-                    "C. Ruppersberger" => {
-                        per.name = "Dutch Ruppersberger".into();
-                    }
-                    "W. Steube" => {
-                        per.name = "Greg Steube".into();
-                    }
-                    "" => {}
-                    _ => {}
-                }
-
                 per.url = tbl_elm.select(&url_sel).next().map_or(String::new(), |a| {
                     a.value()
                         .attr("href")
@@ -149,7 +102,8 @@ impl House {
         Ok(pers)
     }
 
-    pub async fn fetch_adrs(&mut self) -> Result<()> {
+    /// Fetch addresses for members without an address.
+    pub async fn fetch_member_adrs(&mut self) -> Result<()> {
         // Clone self for file writing.
         let mut self_clone = self.clone();
         let per_len = self.persons.len() as f64;
@@ -219,17 +173,54 @@ impl House {
             }
 
             // Checkpoint save.
-            write_to_file(&self, FLE_PTH)?;
+            self.save()?;
         }
 
         Ok(())
     }
 }
 
+impl Source for House {
+    fn key(&self) -> &'static str {
+        KEY_HOUSE
+    }
+
+    fn file_path(&self) -> &'static str {
+        FLE_PTH
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn role(&self) -> Role {
+        self.role.clone()
+    }
+
+    fn persons(&self) -> &[Person] {
+        &self.persons
+    }
+
+    fn persons_mut(&mut self) -> &mut Vec<Person> {
+        &mut self.persons
+    }
+
+    fn fetch_members(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(async move {
+            self.persons = self.fetch_members_list().await?;
+            Ok(())
+        })
+    }
+
+    fn fetch_adrs(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(self.fetch_member_adrs())
+    }
+}
+
 /// Fetch and parse addresses and standardize with the USPS.
 pub async fn fetch_prs_std_adrs(per: &Person, url: &str) -> Result<Option<Vec<Address>>> {
     // Fetch html.
-    let html = fetch_html(url).await?;
+    let html = fetch_html(url, KEY_HOUSE).await?;
 
     // Parse html to address lines.
     let adr_lnes_o = prs_adr_lnes(per, &html);
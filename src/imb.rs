@@ -0,0 +1,532 @@
+use anyhow::{anyhow, Result};
+
+/// The number of bars in an Intelligent Mail barcode.
+pub const BAR_CNT: usize = 65;
+
+/// The number of digits in an IMb tracking code.
+pub const LEN_TRACKING: usize = 20;
+
+lazy_static! {
+    /// Thirteen-bit characters with five bits set.
+    ///
+    /// Codewords 0-1286 map to this table.
+    static ref TBL_5OF13: Vec<u16> = nof13_table(5, 1287);
+    /// Thirteen-bit characters with two bits set.
+    ///
+    /// Codewords 1287-1364 map to this table.
+    static ref TBL_2OF13: Vec<u16> = nof13_table(2, 78);
+}
+
+/// Bar-to-character mapping.
+///
+/// Each bar is `[descender char, descender bit, ascender char, ascender bit]`
+/// where chars `A`-`J` are indexes 0-9.
+///
+/// From "USPS-B-3200 Intelligent Mail Barcode 4-State" specification,
+/// bar to character mapping table.
+const BAR_MAP: [[usize; 4]; BAR_CNT] = [
+    [7, 2, 4, 3],
+    [1, 10, 0, 0],
+    [9, 12, 2, 8],
+    [5, 5, 6, 11],
+    [8, 9, 3, 1],
+    [0, 1, 5, 12],
+    [2, 5, 1, 8],
+    [4, 4, 9, 11],
+    [6, 3, 8, 10],
+    [3, 9, 7, 6],
+    [5, 11, 1, 4],
+    [8, 5, 2, 12],
+    [9, 10, 0, 2],
+    [7, 1, 6, 7],
+    [3, 6, 4, 9],
+    [0, 3, 8, 6],
+    [6, 4, 2, 7],
+    [1, 1, 9, 9],
+    [7, 10, 5, 2],
+    [4, 0, 3, 8],
+    [6, 2, 0, 4],
+    [8, 11, 1, 0],
+    [9, 8, 3, 12],
+    [2, 6, 7, 7],
+    [5, 1, 4, 10],
+    [1, 12, 6, 9],
+    [7, 3, 8, 0],
+    [5, 8, 9, 7],
+    [4, 6, 2, 10],
+    [3, 4, 0, 5],
+    [8, 4, 5, 7],
+    [7, 11, 1, 9],
+    [6, 0, 9, 6],
+    [0, 6, 4, 8],
+    [2, 1, 3, 2],
+    [5, 9, 8, 12],
+    [4, 11, 6, 1],
+    [9, 5, 7, 4],
+    [3, 3, 1, 2],
+    [0, 7, 2, 0],
+    [1, 3, 4, 1],
+    [6, 10, 3, 5],
+    [8, 7, 9, 4],
+    [2, 11, 5, 6],
+    [0, 8, 7, 12],
+    [4, 2, 8, 1],
+    [5, 10, 3, 0],
+    [9, 3, 0, 9],
+    [6, 5, 2, 4],
+    [7, 8, 1, 7],
+    [5, 0, 4, 5],
+    [2, 3, 0, 10],
+    [6, 12, 9, 2],
+    [3, 11, 1, 6],
+    [8, 8, 7, 9],
+    [5, 4, 0, 11],
+    [1, 5, 2, 2],
+    [9, 1, 4, 12],
+    [8, 3, 6, 6],
+    [7, 0, 3, 7],
+    [4, 7, 7, 5],
+    [0, 12, 1, 11],
+    [2, 9, 9, 0],
+    [6, 8, 5, 3],
+    [3, 10, 8, 2],
+];
+
+/// Encodes a 20-digit tracking code and an optional routing code
+/// to 65 characters `F`,`A`,`D`,`T` for use with a barcode font.
+///
+/// Callers validate the individual tracking code fields.
+///
+/// See "USPS-B-3200 Intelligent Mail Barcode 4-State" specification.
+pub fn imb_encode(tracking_code: &str, routing_code: &str) -> Result<String> {
+    // Convert the tracking and routing codes to a binary value.
+    let val = to_binary(tracking_code, routing_code)?;
+
+    // Generate the frame check sequence from the binary value.
+    let fcs = crc11(val);
+
+    // Convert the binary value to codewords.
+    let mut cws = to_codewords(val)?;
+
+    // Insert orientation and frame check information.
+    cws[9] *= 2;
+    if fcs & 0x400 != 0 {
+        cws[0] += 659;
+    }
+
+    // Convert codewords to characters.
+    let mut chrs = [0u16; 10];
+    for (idx, cw) in cws.iter().enumerate() {
+        chrs[idx] = if *cw < 1287 {
+            TBL_5OF13[*cw as usize]
+        } else {
+            TBL_2OF13[(*cw - 1287) as usize]
+        };
+        // Invert a character when its frame check bit is set.
+        if fcs & (1 << idx) != 0 {
+            chrs[idx] ^= 0x1FFF;
+        }
+    }
+
+    // Map characters to bars.
+    Ok(BAR_MAP
+        .iter()
+        .map(|[dsc_chr, dsc_bit, asc_chr, asc_bit]| {
+            let dsc = chrs[*dsc_chr] & (1 << dsc_bit) != 0;
+            let asc = chrs[*asc_chr] & (1 << asc_bit) != 0;
+            match (dsc, asc) {
+                (true, true) => 'F',
+                (false, true) => 'A',
+                (true, false) => 'D',
+                (false, false) => 'T',
+            }
+        })
+        .collect())
+}
+
+/// Mailing information decoded from an Intelligent Mail barcode.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImbData {
+    pub barcode_id: String,
+    pub service_id: String,
+    pub mailer_id: String,
+    pub serial_id: String,
+    pub routing_code: String,
+}
+
+impl ImbData {
+    /// The 20-digit tracking code.
+    pub fn tracking_code(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            self.barcode_id, self.service_id, self.mailer_id, self.serial_id
+        )
+    }
+}
+
+/// Decodes 65 characters `F`,`A`,`D`,`T` to mailing information.
+///
+/// Returns an error when a character is invalid or the
+/// frame check sequence doesn't match.
+///
+/// A mailer ID starting with `9` is nine digits; otherwise it's six digits.
+pub fn imb_decode(barcode: &str) -> Result<ImbData> {
+    if barcode.len() != BAR_CNT {
+        return Err(anyhow!(
+            "Invalid barcode length {} (expected {})",
+            barcode.len(),
+            BAR_CNT
+        ));
+    }
+
+    // Map bars to characters.
+    let mut chrs = [0u16; 10];
+    for (bar, [dsc_chr, dsc_bit, asc_chr, asc_bit]) in barcode.chars().zip(BAR_MAP.iter()) {
+        let (dsc, asc) = match bar {
+            'F' => (true, true),
+            'A' => (false, true),
+            'D' => (true, false),
+            'T' => (false, false),
+            _ => return Err(anyhow!("Invalid barcode character {:?}", bar)),
+        };
+        if dsc {
+            chrs[*dsc_chr] |= 1 << dsc_bit;
+        }
+        if asc {
+            chrs[*asc_chr] |= 1 << asc_bit;
+        }
+    }
+
+    // Convert characters to codewords.
+    // An inverted character holds a frame check bit.
+    let mut fcs: u16 = 0;
+    let mut cws = [0u16; 10];
+    for (idx, chr) in chrs.iter().enumerate() {
+        let (chr, is_inv) = match chr.count_ones() {
+            2 | 5 => (*chr, false),
+            8 | 11 => (*chr ^ 0x1FFF, true),
+            _ => return Err(anyhow!("Invalid barcode character at index {}", idx)),
+        };
+        if is_inv {
+            fcs |= 1 << idx;
+        }
+        cws[idx] = if let Some(pos) = TBL_5OF13.iter().position(|c| *c == chr) {
+            pos as u16
+        } else if let Some(pos) = TBL_2OF13.iter().position(|c| *c == chr) {
+            pos as u16 + 1287
+        } else {
+            return Err(anyhow!("Invalid barcode character at index {}", idx));
+        };
+    }
+
+    // Extract orientation and frame check information.
+    if cws[0] > 658 {
+        cws[0] -= 659;
+        fcs |= 0x400;
+    }
+    if cws[9] % 2 != 0 {
+        return Err(anyhow!("Invalid barcode orientation"));
+    }
+    cws[9] /= 2;
+
+    // Convert codewords to a binary value.
+    let val = from_codewords(&cws)?;
+
+    // Check the frame check sequence.
+    if crc11(val) != fcs {
+        return Err(anyhow!("Barcode frame check sequence mismatch"));
+    }
+
+    from_binary(val)
+}
+
+/// Converts a tracking code and routing code to a 102-bit binary value.
+fn to_binary(tracking_code: &str, routing_code: &str) -> Result<u128> {
+    if tracking_code.len() != LEN_TRACKING || !tracking_code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("Invalid tracking_code"));
+    }
+    if !routing_code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("Invalid routing_code"));
+    }
+
+    // Offset the routing code by its length so each length has a unique range.
+    let mut val: u128 = match routing_code.len() {
+        0 => 0,
+        5 => routing_code.parse::<u128>()? + 1,
+        9 => routing_code.parse::<u128>()? + 100_000 + 1,
+        11 => routing_code.parse::<u128>()? + 1_000_000_000 + 100_000 + 1,
+        _ => return Err(anyhow!("Invalid routing_code")),
+    };
+
+    // The second barcode identifier digit is 0-4.
+    let digits: Vec<u128> = tracking_code.bytes().map(|b| (b - b'0') as u128).collect();
+    if digits[1] > 4 {
+        return Err(anyhow!("Invalid tracking_code"));
+    }
+    val = val * 10 + digits[0];
+    val = val * 5 + digits[1];
+    for digit in &digits[2..] {
+        val = val * 10 + digit;
+    }
+
+    Ok(val)
+}
+
+/// Converts a 102-bit binary value to mailing information.
+fn from_binary(mut val: u128) -> Result<ImbData> {
+    // Extract tracking code digits in reverse order.
+    let mut digits = [0u8; LEN_TRACKING];
+    for idx in (2..LEN_TRACKING).rev() {
+        digits[idx] = (val % 10) as u8;
+        val /= 10;
+    }
+    digits[1] = (val % 5) as u8;
+    val /= 5;
+    digits[0] = (val % 10) as u8;
+    val /= 10;
+    let tracking_code: String = digits.iter().map(|d| (b'0' + d) as char).collect();
+
+    // Remove the routing code offset.
+    let routing_code = match val {
+        0 => String::new(),
+        1..=100_000 => format!("{:05}", val - 1),
+        100_001..=1_000_100_000 => format!("{:09}", val - 100_000 - 1),
+        1_000_100_001..=101_000_100_000 => {
+            format!("{:011}", val - 1_000_000_000 - 100_000 - 1)
+        }
+        _ => return Err(anyhow!("Invalid routing_code")),
+    };
+
+    // Mailer IDs starting with 9 are nine digits.
+    let len_mailer_id = if tracking_code.as_bytes()[5] == b'9' {
+        9
+    } else {
+        6
+    };
+
+    Ok(ImbData {
+        barcode_id: tracking_code[..2].into(),
+        service_id: tracking_code[2..5].into(),
+        mailer_id: tracking_code[5..5 + len_mailer_id].into(),
+        serial_id: tracking_code[5 + len_mailer_id..].into(),
+        routing_code,
+    })
+}
+
+/// Generates an 11-bit CRC frame check sequence from a 102-bit binary value.
+fn crc11(val: u128) -> u16 {
+    const POLY: u16 = 0x0F35;
+    let bytes = &val.to_be_bytes()[3..];
+    let mut fcs: u16 = 0x07FF;
+
+    // The most significant byte skips its two most significant bits.
+    let mut data = (bytes[0] as u16) << 5;
+    for _ in 2..8 {
+        fcs = crc11_step(fcs, data, POLY);
+        data <<= 1;
+    }
+    for byte in &bytes[1..] {
+        let mut data = (*byte as u16) << 3;
+        for _ in 0..8 {
+            fcs = crc11_step(fcs, data, POLY);
+            data <<= 1;
+        }
+    }
+
+    fcs
+}
+
+fn crc11_step(fcs: u16, data: u16, poly: u16) -> u16 {
+    let ret = if (fcs ^ data) & 0x400 != 0 {
+        (fcs << 1) ^ poly
+    } else {
+        fcs << 1
+    };
+    ret & 0x7FF
+}
+
+/// Converts a binary value to ten codewords.
+///
+/// Codeword `A` is 0-658, `B`-`I` are 0-1364, and `J` is 0-635.
+fn to_codewords(mut val: u128) -> Result<[u16; 10]> {
+    let mut cws = [0u16; 10];
+    cws[9] = (val % 636) as u16;
+    val /= 636;
+    for idx in (1..9).rev() {
+        cws[idx] = (val % 1365) as u16;
+        val /= 1365;
+    }
+    if val > 658 {
+        return Err(anyhow!("Binary value out of range"));
+    }
+    cws[0] = val as u16;
+    Ok(cws)
+}
+
+/// Converts ten codewords to a binary value.
+fn from_codewords(cws: &[u16; 10]) -> Result<u128> {
+    if cws[0] > 658 || cws[1..9].iter().any(|cw| *cw > 1364) || cws[9] > 635 {
+        return Err(anyhow!("Codeword out of range"));
+    }
+    let mut val = cws[0] as u128;
+    for cw in &cws[1..9] {
+        val = val * 1365 + *cw as u128;
+    }
+    Ok(val * 636 + cws[9] as u128)
+}
+
+/// Generates a table of thirteen-bit characters with `n` bits set.
+///
+/// Characters and their bit-reversals are placed in pairs from the start
+/// of the table. Palindromic characters are placed from the end.
+fn nof13_table(n: u32, len: usize) -> Vec<u16> {
+    let mut ret = vec![0u16; len];
+    let mut idx_lo = 0;
+    let mut idx_hi = len - 1;
+    for chr in 0u16..8192 {
+        if chr.count_ones() != n {
+            continue;
+        }
+        let rev = chr.reverse_bits() >> 3;
+        if rev < chr {
+            continue;
+        }
+        if rev == chr {
+            ret[idx_hi] = chr;
+            idx_hi -= 1;
+        } else {
+            ret[idx_lo] = chr;
+            ret[idx_lo + 1] = rev;
+            idx_lo += 2;
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Examples from the "USPS-B-3200 Intelligent Mail Barcode 4-State" specification.
+    const TRACKING: &str = "01234567094987654321";
+
+    #[test]
+    fn test_imb_encode_no_routing() {
+        assert_eq!(
+            imb_encode(TRACKING, "").unwrap(),
+            "ATTFATTDTTADTAATTDTDTATTDAFDDFADFDFTFFFFFTATFAAAATDFFTDAADFTFDTDT"
+        );
+    }
+
+    #[test]
+    fn test_imb_encode_zip5() {
+        assert_eq!(
+            imb_encode(TRACKING, "01234").unwrap(),
+            "DTTAFADDTTFTDTFTFDTDDADADAFADFATDDFTAAAFDTTADFAAATDFDTDFADDDTDFFT"
+        );
+    }
+
+    #[test]
+    fn test_imb_encode_zip9() {
+        assert_eq!(
+            imb_encode(TRACKING, "012345678").unwrap(),
+            "ADFTTAFDTTTTFATTADTAAATFTFTATDAAAFDDADATATDTDTTDFDTDATADADTDFFTFA"
+        );
+    }
+
+    #[test]
+    fn test_imb_encode_zip11() {
+        assert_eq!(
+            imb_encode(TRACKING, "01234567891").unwrap(),
+            "AADTFFDFTDADTAADAATFDTDDAAADDTDTTDAFADADDDTFFFDDTTTADFAAADFTDAADA"
+        );
+    }
+
+    #[test]
+    fn test_imb_encode_invalid() {
+        assert!(imb_encode("0123456709498765432", "").is_err());
+        assert!(imb_encode("0123456709498765432a", "").is_err());
+        assert!(imb_encode("05234567094987654321", "").is_err());
+        assert!(imb_encode(TRACKING, "0123").is_err());
+        assert!(imb_encode(TRACKING, "0123a").is_err());
+    }
+
+    #[test]
+    fn test_imb_decode() {
+        for routing_code in ["", "01234", "012345678", "01234567891"] {
+            let barcode = imb_encode(TRACKING, routing_code).unwrap();
+            let data = imb_decode(&barcode).unwrap();
+            assert_eq!(data.tracking_code(), TRACKING);
+            assert_eq!(data.routing_code, routing_code);
+        }
+    }
+
+    #[test]
+    fn test_imb_decode_fields() {
+        let data = imb_decode(&imb_encode(TRACKING, "01234").unwrap()).unwrap();
+        assert_eq!(
+            data,
+            ImbData {
+                barcode_id: "01".into(),
+                service_id: "234".into(),
+                mailer_id: "567094".into(),
+                serial_id: "987654321".into(),
+                routing_code: "01234".into(),
+            }
+        );
+
+        let data = imb_decode(&imb_encode("50272912345678123456", "20500000500").unwrap()).unwrap();
+        assert_eq!(
+            data,
+            ImbData {
+                barcode_id: "50".into(),
+                service_id: "272".into(),
+                mailer_id: "912345678".into(),
+                serial_id: "123456".into(),
+                routing_code: "20500000500".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_imb_decode_invalid() {
+        let barcode = imb_encode(TRACKING, "01234567891").unwrap();
+
+        // Wrong length.
+        assert!(imb_decode(&barcode[1..]).is_err());
+
+        // Invalid character.
+        let mut chrs: Vec<char> = barcode.chars().collect();
+        chrs[10] = 'X';
+        assert!(imb_decode(&chrs.iter().collect::<String>()).is_err());
+
+        // Every single-bar change is detected.
+        for idx in 0..BAR_CNT {
+            for bar in ['F', 'A', 'D', 'T'] {
+                let mut chrs: Vec<char> = barcode.chars().collect();
+                if chrs[idx] == bar {
+                    continue;
+                }
+                chrs[idx] = bar;
+                assert!(imb_decode(&chrs.iter().collect::<String>()).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_binary_routing_max() {
+        // The largest routing code decodes; one past it is rejected.
+        let val = to_binary(TRACKING, "99999999999").unwrap();
+        assert_eq!(from_binary(val).unwrap().routing_code, "99999999999");
+        let routing_unit = 10 * 5 * 10u128.pow(LEN_TRACKING as u32 - 2);
+        assert!(from_binary(val + routing_unit).is_err());
+    }
+
+    #[test]
+    fn test_nof13_table() {
+        assert_eq!(TBL_5OF13.len(), 1287);
+        assert_eq!(TBL_2OF13.len(), 78);
+        assert!(TBL_5OF13.iter().all(|c| c.count_ones() == 5));
+        assert!(TBL_2OF13.iter().all(|c| c.count_ones() == 2));
+    }
+}
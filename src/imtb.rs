@@ -0,0 +1,228 @@
+use anyhow::{anyhow, Result};
+
+/// The number of digits in an Intelligent Mail tray barcode.
+pub const LEN_IMTB: usize = 24;
+
+/// Code 128 start character for code set C.
+const START_C: usize = 105;
+
+/// Code 128 stop character.
+const STOP: usize = 106;
+
+/// Code 128 bar and space widths in modules, starting with a bar.
+///
+/// Values 0-102 are data, 103-105 are start characters and 106 is the
+/// stop character. Each character is 11 modules wide; the stop
+/// character is 13 modules wide.
+///
+/// From ISO/IEC 15417 Code 128 bar code symbology specification.
+const PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212",
+    "221213", "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221",
+    "223211", "221132", "221231", "213212", "223112", "312131", "311222", "321122", "321221",
+    "312212", "322112", "322211", "212123", "212321", "232121", "111323", "131123", "131321",
+    "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331", "132131",
+    "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131",
+    "311123", "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111",
+    "111224", "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+    "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111", "111242",
+    "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+/// Builds the 24-digit Intelligent Mail tray barcode.
+///
+/// Fields are the 5-digit destination ZIP Code, 3-digit content identifier
+/// number (CIN), 3-digit USPS processing code, mailer ID and serial number.
+/// A 6-digit mailer ID has a 7-digit serial; a 9-digit mailer ID has a
+/// 4-digit serial. A 3-digit destination ZIP Code is padded with zeros.
+///
+/// See "Intelligent Mail Tray Barcode" specification USPS-B-3001.
+pub fn imtb_digits(
+    dest_zip: &str,
+    cin: &str,
+    proc_code: &str,
+    mailer_id: &str,
+    serial: u32,
+) -> Result<String> {
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+
+    // Validate destination ZIP Code.
+    if !is_digits(dest_zip) || (dest_zip.len() != 3 && dest_zip.len() != 5) {
+        return Err(anyhow!("invalid tray destination ZIP Code {:?}", dest_zip));
+    }
+
+    // Validate CIN and processing code.
+    if !is_digits(cin) || cin.len() != 3 {
+        return Err(anyhow!("invalid content identifier number {:?}", cin));
+    }
+    if !is_digits(proc_code) || proc_code.len() != 3 {
+        return Err(anyhow!("invalid processing code {:?}", proc_code));
+    }
+
+    // Validate serial.
+    let serial_len = imtb_serial_len(mailer_id)?;
+    if serial >= 10u32.pow(serial_len as u32) {
+        return Err(anyhow!(
+            "tray serial {} exceeds {} digits",
+            serial,
+            serial_len
+        ));
+    }
+
+    Ok(format!(
+        "{:0<5}{}{}{}{:0width$}",
+        dest_zip,
+        cin,
+        proc_code,
+        mailer_id,
+        serial,
+        width = serial_len
+    ))
+}
+
+/// The number of tray serial digits for a mailer ID.
+///
+/// 6-digit mailer IDs start with 0-8 and have a 7-digit serial;
+/// 9-digit mailer IDs start with 9 and have a 4-digit serial.
+pub fn imtb_serial_len(mailer_id: &str) -> Result<usize> {
+    let serial_len = match (mailer_id.len(), mailer_id.chars().next()) {
+        (6, Some(c)) if c != '9' => 7,
+        (9, Some('9')) => 4,
+        _ => 0,
+    };
+    if !mailer_id.chars().all(|c| c.is_ascii_digit()) || serial_len == 0 {
+        return Err(anyhow!("invalid mailer ID {:?}", mailer_id));
+    }
+    Ok(serial_len)
+}
+
+/// Encodes digits as Code 128 code set C.
+///
+/// Returns bar and space widths in modules, starting with a bar,
+/// including the start, check and stop characters.
+/// Quiet zones are not included.
+pub fn code128c_encode(digits: &str) -> Result<Vec<u8>> {
+    if digits.is_empty()
+        || !digits.len().is_multiple_of(2)
+        || !digits.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(anyhow!("invalid code 128 digits {:?}", digits));
+    }
+
+    // Code set C encodes two digits per character.
+    let mut vals = vec![START_C];
+    for idx in (0..digits.len()).step_by(2) {
+        vals.push(digits[idx..idx + 2].parse::<usize>()?);
+    }
+
+    // Add the modulo 103 check character.
+    // The start character has weight 1, as does the first data character.
+    let chk = vals
+        .iter()
+        .enumerate()
+        .map(|(idx, val)| idx.max(1) * val)
+        .sum::<usize>()
+        % 103;
+    vals.push(chk);
+    vals.push(STOP);
+
+    Ok(vals
+        .iter()
+        .flat_map(|val| PATTERNS[*val].bytes().map(|b| b - b'0'))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns() {
+        for (idx, pattern) in PATTERNS.iter().enumerate() {
+            let widths: Vec<u8> = pattern.bytes().map(|b| b - b'0').collect();
+            let modules: u8 = widths.iter().sum();
+            assert_eq!(modules, if idx == STOP { 13 } else { 11 }, "{idx}");
+            // Bars have an even module count.
+            let bars: u8 = widths.iter().step_by(2).sum();
+            assert_eq!(bars % 2, 0, "{idx}");
+        }
+        // Patterns are unique.
+        let mut sorted = PATTERNS.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), PATTERNS.len());
+    }
+
+    #[test]
+    fn test_code128c_encode() {
+        let widths = code128c_encode("123456").unwrap();
+        let pattern = |val: usize| -> Vec<u8> { PATTERNS[val].bytes().map(|b| b - b'0').collect() };
+
+        // Start C, 12, 34, 56, check 44, stop.
+        let mut expected = Vec::new();
+        for val in [START_C, 12, 34, 56, 44, STOP] {
+            expected.extend(pattern(val));
+        }
+        assert_eq!(widths, expected);
+
+        // 24 digits are 12 characters plus start, check and stop.
+        let widths = code128c_encode(&"0".repeat(LEN_IMTB)).unwrap();
+        assert_eq!(
+            widths.iter().map(|o| *o as usize).sum::<usize>(),
+            14 * 11 + 13
+        );
+    }
+
+    #[test]
+    fn test_code128c_encode_invalid() {
+        let cases = vec!["", "123", "12a4"];
+
+        for digits in cases {
+            assert!(code128c_encode(digits).is_err(), "{digits}");
+        }
+    }
+
+    #[test]
+    fn test_imtb_digits() {
+        let cases = vec![
+            (
+                ("20510", "551", "000", "123456", 7),
+                "205105510001234560000007",
+            ),
+            (
+                ("205", "554", "000", "901234567", 42),
+                "205005540009012345670042",
+            ),
+        ];
+
+        for ((dest_zip, cin, proc_code, mailer_id, serial), expected) in cases {
+            let digits = imtb_digits(dest_zip, cin, proc_code, mailer_id, serial).unwrap();
+            assert_eq!(digits, expected);
+            assert_eq!(digits.len(), LEN_IMTB);
+        }
+    }
+
+    #[test]
+    fn test_imtb_digits_invalid() {
+        let cases = vec![
+            ("2051", "551", "000", "123456", 7),
+            ("2051a", "551", "000", "123456", 7),
+            ("20510", "55", "000", "123456", 7),
+            ("20510", "551", "0000", "123456", 7),
+            // 6-digit mailer IDs do not start with 9.
+            ("20510", "551", "000", "923456", 7),
+            ("20510", "551", "000", "12345678", 7),
+            ("20510", "551", "000", "901234567", 10_000),
+            ("20510", "551", "000", "123456", 10_000_000),
+        ];
+
+        for (dest_zip, cin, proc_code, mailer_id, serial) in cases {
+            assert!(
+                imtb_digits(dest_zip, cin, proc_code, mailer_id, serial).is_err(),
+                "{dest_zip} {cin} {proc_code} {mailer_id} {serial}"
+            );
+        }
+    }
+}
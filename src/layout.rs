@@ -0,0 +1,564 @@
+use crate::envelope::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Millimeters per point.
+pub const MM_PER_PT: f32 = 25.4 / 72.0;
+
+/// Return address margin from the upper left corner.
+const FROM_MARGIN: f32 = 10.0;
+
+// Indicia text position from the upper right corner of the envelope.
+const INDICIA_MARGIN_X: f32 = 34.0;
+const INDICIA_MARGIN_Y: f32 = 9.0;
+/// Indicia line height in points.
+pub const INDICIA_LINE_HEIGHT: f32 = 10.0;
+
+// Letter OCR read area and barcode clear zone.
+// See USPS Publication 25, Designing Letter and Reply Mail.
+const OCR_MARGIN_X: f32 = 12.7;
+const OCR_TOP: f32 = 69.85;
+const CLEAR_ZONE_WIDTH: f32 = 120.65;
+const CLEAR_ZONE_HEIGHT: f32 = 15.875;
+
+/// Intelligent Mail barcode length, at most 3-1/4 inches.
+const IMB_LEN: f32 = 82.55;
+/// Intelligent Mail barcode full bar height, at most 0.165 inch.
+const IMB_HEIGHT: f32 = 4.2;
+
+/// Space between a window edge and the barcode, 1/8 inch.
+///
+/// See DMM 202.5.4 window envelopes.
+const WINDOW_CLEARANCE: f32 = 3.175;
+
+/// Helvetica ascent as a fraction of the font size.
+const ASCENT: f32 = 0.75;
+
+/// Space between placed elements.
+const GAP: f32 = 3.0;
+
+/// Helvetica glyph widths for ASCII 32-126, in 1/1000 em.
+///
+/// From the Adobe Helvetica font metrics.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// A rectangle in millimeters from the lower left corner of the envelope.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub llx: f32,
+    pub lly: f32,
+    pub urx: f32,
+    pub ury: f32,
+}
+
+impl Area {
+    /// Returns true when the other area lies inside this area, within
+    /// 0.001 mm.
+    pub fn contains(&self, othr: &Area) -> bool {
+        const EPS: f32 = 0.001;
+        othr.llx + EPS >= self.llx
+            && othr.lly + EPS >= self.lly
+            && othr.urx <= self.urx + EPS
+            && othr.ury <= self.ury + EPS
+    }
+
+    /// Returns true when the areas overlap.
+    pub fn intersects(&self, othr: &Area) -> bool {
+        self.llx < othr.urx && othr.llx < self.urx && self.lly < othr.ury && othr.lly < self.ury
+    }
+}
+
+/// An envelope size, in landscape.
+///
+/// Letter-size envelopes only; flats such as 9 x 12 inch envelopes are
+/// out of scope.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvelopeSpec {
+    /// A #10 envelope, 9-1/2 x 4-1/8 inches.
+    #[default]
+    No10,
+    /// A #9 envelope, 8-7/8 x 3-7/8 inches.
+    No9,
+    /// A 6 x 9 inch envelope, mailed as a letter.
+    SixByNine,
+    /// A #10 double-window envelope.
+    ///
+    /// The letter shows the addresses through the window.
+    No10Window,
+}
+
+impl EnvelopeSpec {
+    /// Width and height in millimeters.
+    pub fn size(&self) -> (f32, f32) {
+        match self {
+            EnvelopeSpec::No10 | EnvelopeSpec::No10Window => (241.3, 104.8),
+            EnvelopeSpec::No9 => (225.4, 98.4),
+            EnvelopeSpec::SixByNine => (228.6, 152.4),
+        }
+    }
+
+    /// Returns true for window envelopes, which show the addresses printed
+    /// on the letter.
+    pub fn is_window(&self) -> bool {
+        matches!(self, EnvelopeSpec::No10Window)
+    }
+}
+
+/// Double-window envelope geometry, in millimeters from the lower left
+/// corner of the envelope.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Windows {
+    /// The return address window.
+    pub from: Area,
+    /// The recipient address window.
+    pub to: Area,
+}
+
+impl Default for Windows {
+    /// A common #10 double-window envelope.
+    ///
+    /// The return address window is 3-1/2 x 1 inches, 3/8 inch from the left
+    /// and 5/8 inch from the top. The recipient window is 4-1/2 x 1-1/8
+    /// inches, 3/4 inch from the left and 5/8 inch from the bottom.
+    fn default() -> Self {
+        Windows {
+            from: Area {
+                llx: 9.5,
+                lly: 63.5,
+                urx: 98.4,
+                ury: 88.9,
+            },
+            to: Area {
+                llx: 19.1,
+                lly: 15.9,
+                urx: 133.4,
+                ury: 44.5,
+            },
+        }
+    }
+}
+
+impl fmt::Display for EnvelopeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnvelopeSpec::No10 => write!(f, "#10 envelope"),
+            EnvelopeSpec::No9 => write!(f, "#9 envelope"),
+            EnvelopeSpec::SixByNine => write!(f, "6x9 envelope"),
+            EnvelopeSpec::No10Window => write!(f, "#10 window envelope"),
+        }
+    }
+}
+
+/// Positions of each envelope element, in millimeters from the lower left
+/// corner.
+///
+/// Text positions are the first baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub width: f32,
+    pub height: f32,
+    pub from: (f32, f32),
+    pub from_font_size: f32,
+    pub from_lne_height: f32,
+    pub adr: (f32, f32),
+    pub adr_font_size: f32,
+    pub adr_lne_height: f32,
+    /// The barcode below the address lines.
+    pub barcode: Area,
+    /// The lower right area kept clear for the barcode reader.
+    pub clear_zone: Area,
+    pub indicia: (f32, f32),
+    /// The outline around a permit indicia.
+    pub indicia_box: Area,
+    pub endorsement: Option<(f32, f32)>,
+}
+
+impl Layout {
+    /// Places each element on an envelope.
+    ///
+    /// Window envelopes place the addresses in the windows.
+    /// The address lines exclude the barcode, which is placed below them.
+    /// Fails when an address block or the endorsement does not fit.
+    pub fn new(
+        spec: EnvelopeSpec,
+        windows: &Windows,
+        from_lnes: &[String],
+        adr_lnes: &[String],
+        indicia_lne_cnt: usize,
+        endorsement: Option<(&str, EndorsementPos)>,
+    ) -> Result<Layout> {
+        let (width, height) = spec.size();
+
+        // Place the indicia in the upper right corner.
+        let indicia = (width - INDICIA_MARGIN_X, height - INDICIA_MARGIN_Y);
+        let indicia_lnes_height =
+            pt_to_mm(INDICIA_LINE_HEIGHT * indicia_lne_cnt.saturating_sub(1) as f32);
+        let indicia_box = Area {
+            llx: indicia.0 - 2.0,
+            lly: indicia.1 - indicia_lnes_height - GAP,
+            urx: width - 5.0,
+            ury: height - 5.0,
+        };
+
+        // Place the return address in the upper left corner.
+        let (from_area, from_font_size, from_lne_height) = if spec.is_window() {
+            (inset(&windows.from, WINDOW_CLEARANCE), 9.0, 10.0)
+        } else {
+            let area = Area {
+                llx: FROM_MARGIN,
+                lly: height / 2.0,
+                urx: indicia_box.llx - GAP,
+                ury: height - FROM_MARGIN + pt_to_mm(10.0) * ASCENT,
+            };
+            (area, 10.0, 12.0)
+        };
+        let from_blk = Block::top(&from_area, from_lnes, from_font_size, from_lne_height)
+            .map_err(|err| anyhow!("return address {}", err))?;
+        let from = (from_area.llx, from_blk.y);
+        let from_bottom = from_blk.bottom;
+
+        // Letters keep the lower right corner clear for the barcode reader.
+        let clear_zone = Area {
+            llx: width - CLEAR_ZONE_WIDTH,
+            lly: 0.0,
+            urx: width,
+            ury: CLEAR_ZONE_HEIGHT,
+        };
+
+        // Find the area for the address block.
+        // The block stays in the OCR read area, above the clear zone.
+        // Windows keep the barcode 1/8 inch from each window edge.
+        let top = from_bottom.min(indicia_box.lly) - GAP;
+        let (adr_area, adr_font_size, adr_lne_height) = if spec.is_window() {
+            (inset(&windows.to, WINDOW_CLEARANCE), 9.0, 10.0)
+        } else {
+            let area = Area {
+                llx: width * 0.35,
+                lly: CLEAR_ZONE_HEIGHT + GAP,
+                urx: width - OCR_MARGIN_X,
+                ury: top.min(OCR_TOP),
+            };
+            (area, 12.0, 18.0)
+        };
+
+        // Place the barcode at the bottom of the address area, with the
+        // address lines above it.
+        let barcode = Area {
+            llx: adr_area.llx,
+            lly: adr_area.lly,
+            urx: adr_area.llx + IMB_LEN,
+            ury: adr_area.lly + IMB_HEIGHT,
+        };
+        if spec.is_window() {
+            let barcode_zone = inset(&barcode, -WINDOW_CLEARANCE);
+            if !windows.to.contains(&barcode_zone) {
+                return Err(anyhow!(
+                    "barcode clear zone {:?} is outside the address window {:?}",
+                    barcode_zone,
+                    windows.to
+                ));
+            }
+        } else if barcode.urx > adr_area.urx {
+            return Err(anyhow!("barcode does not fit the {} address area", spec));
+        }
+        let lnes_area = Area {
+            lly: barcode.ury + GAP / 2.0,
+            ..adr_area
+        };
+        let adr_blk = Block::bottom(&lnes_area, adr_lnes, adr_font_size, adr_lne_height)
+            .map_err(|err| anyhow!("address block {} on the {}", err, spec))?;
+        let adr = (adr_area.llx, adr_blk.y);
+
+        // Place the endorsement.
+        // See DMM 507.1.5.3 endorsement placement.
+        let ascent = pt_to_mm(adr_font_size) * ASCENT;
+        let endorsement = match endorsement {
+            None => None,
+            Some((txt, pos)) => {
+                let (x, y) = match pos {
+                    EndorsementPos::BelowReturnAdr => {
+                        (from.0, from_bottom - pt_to_mm(from_lne_height))
+                    }
+                    EndorsementPos::AboveAdr => (adr.0, adr.1 + ascent + GAP),
+                    EndorsementPos::LeftOfPostage => {
+                        (indicia_box.llx - GAP - text_width(txt, 8.0), indicia.1)
+                    }
+                    EndorsementPos::BelowPostage => (
+                        indicia_box.urx - text_width(txt, 8.0),
+                        indicia_box.lly - 4.0,
+                    ),
+                };
+                let area = Area {
+                    llx: x,
+                    lly: y,
+                    urx: x + text_width(txt, 8.0),
+                    ury: y + pt_to_mm(8.0),
+                };
+                let page = Area {
+                    llx: 0.0,
+                    lly: 0.0,
+                    urx: width,
+                    ury: height,
+                };
+                if !page.contains(&area) || clear_zone.intersects(&area) {
+                    return Err(anyhow!("{:?} endorsement does not fit the {}", pos, spec));
+                }
+                Some((x, y))
+            }
+        };
+
+        Ok(Layout {
+            width,
+            height,
+            from,
+            from_font_size,
+            from_lne_height,
+            adr,
+            adr_font_size,
+            adr_lne_height,
+            barcode,
+            clear_zone,
+            indicia,
+            indicia_box,
+            endorsement,
+        })
+    }
+}
+
+/// Lines of text placed in an area.
+struct Block {
+    /// The first baseline.
+    y: f32,
+    /// The last baseline.
+    bottom: f32,
+}
+
+impl Block {
+    /// Places lines from the top of an area.
+    fn top(area: &Area, lnes: &[String], font_size: f32, lne_height: f32) -> Result<Block> {
+        let y = area.ury - pt_to_mm(font_size) * ASCENT;
+        Block::new(area, lnes, font_size, lne_height, y)
+    }
+
+    /// Places lines from the bottom of an area.
+    fn bottom(area: &Area, lnes: &[String], font_size: f32, lne_height: f32) -> Result<Block> {
+        let y = area.lly + pt_to_mm(lne_height * lnes.len().saturating_sub(1) as f32);
+        Block::new(area, lnes, font_size, lne_height, y)
+    }
+
+    /// Checks that each line fits the area.
+    fn new(area: &Area, lnes: &[String], font_size: f32, lne_height: f32, y: f32) -> Result<Block> {
+        for lne in lnes.iter() {
+            let lne_width = text_width(lne, font_size);
+            if area.llx + lne_width > area.urx {
+                return Err(anyhow!(
+                    "line {:?} is {:.1} mm wide; {:.1} mm fits",
+                    lne,
+                    lne_width,
+                    area.urx - area.llx
+                ));
+            }
+        }
+        let bottom = y - pt_to_mm(lne_height * lnes.len().saturating_sub(1) as f32);
+        let ascent = pt_to_mm(font_size) * ASCENT;
+        if bottom < area.lly || y + ascent > area.ury {
+            return Err(anyhow!(
+                "of {} lines is {:.1} mm tall; {:.1} mm fits",
+                lnes.len(),
+                y + ascent - bottom,
+                area.ury - area.lly
+            ));
+        }
+        Ok(Block { y, bottom })
+    }
+}
+
+/// Shrinks an area by a margin on each side, or grows it for a negative
+/// margin.
+fn inset(area: &Area, margin: f32) -> Area {
+    Area {
+        llx: area.llx + margin,
+        lly: area.lly + margin,
+        urx: area.urx - margin,
+        ury: area.ury - margin,
+    }
+}
+
+/// Converts points to millimeters.
+pub fn pt_to_mm(pt: f32) -> f32 {
+    pt * MM_PER_PT
+}
+
+/// The width of Helvetica text in millimeters.
+///
+/// Characters outside ASCII take the width of a digit.
+pub fn text_width(txt: &str, font_size: f32) -> f32 {
+    let ems: u32 = txt
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => HELVETICA_WIDTHS[c as usize - 32] as u32,
+            _ => 556,
+        })
+        .sum();
+    pt_to_mm(ems as f32 * font_size / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    fn lnes(title1: &str) -> Vec<String> {
+        vec![
+            "JANE DOE".into(),
+            title1.into(),
+            "100 Constitution Ave NW".into(),
+            "WASHINGTON  DC  20510-0001".into(),
+        ]
+    }
+
+    fn layout(
+        spec: EnvelopeSpec,
+        adr_lnes: &[String],
+        endorsement: Option<(&str, EndorsementPos)>,
+    ) -> Result<Layout> {
+        Layout::new(
+            spec,
+            &Windows::default(),
+            &from_lnes(),
+            adr_lnes,
+            6,
+            endorsement,
+        )
+    }
+
+    #[test]
+    fn test_text_width() {
+        // "AAAA" is four 667/1000 em glyphs.
+        assert!((text_width("AAAA", 12.0) - pt_to_mm(32.016)).abs() < 1e-4);
+        assert_eq!(text_width("", 12.0), 0.0);
+        assert_eq!(text_width("é", 10.0), text_width("0", 10.0));
+    }
+
+    #[test]
+    fn test_layout_specs() {
+        let cases = vec![
+            EnvelopeSpec::No10,
+            EnvelopeSpec::No9,
+            EnvelopeSpec::SixByNine,
+            EnvelopeSpec::No10Window,
+        ];
+
+        for spec in cases {
+            let lyt = layout(
+                spec,
+                &lnes("Senator"),
+                Some(("Return Service Requested", EndorsementPos::BelowPostage)),
+            )
+            .unwrap();
+            // The address block is below the return address and indicia,
+            // and the barcode is below the address block.
+            assert!(lyt.adr.1 < lyt.indicia_box.lly, "{spec}");
+            assert!(lyt.adr.1 < lyt.from.1, "{spec}");
+            let last_y = lyt.adr.1 - pt_to_mm(lyt.adr_lne_height * 3.0);
+            assert!(lyt.barcode.ury < last_y, "{spec}");
+            // The barcode is above the clear zone, or inside the window.
+            if spec.is_window() {
+                assert!(Windows::default().to.contains(&lyt.barcode), "{spec}");
+            } else {
+                assert!(lyt.barcode.lly >= lyt.clear_zone.ury, "{spec}");
+            }
+            // The endorsement is below the indicia.
+            assert!(lyt.endorsement.unwrap().1 < lyt.indicia_box.lly, "{spec}");
+        }
+    }
+
+    #[test]
+    fn test_layout_overflow() {
+        let title1 = "Subcommittee on Oversight, Investigations, and Regulations of the \
+                      Committee on Energy and Natural Resources";
+        let cases = vec![
+            (EnvelopeSpec::No10, lnes("Senator"), true),
+            (EnvelopeSpec::No10, lnes(title1), false),
+            (
+                EnvelopeSpec::No9,
+                lnes("Chairman, Committee on Armed Services"),
+                true,
+            ),
+            // A window holds fewer lines.
+            (EnvelopeSpec::No10Window, vec!["A".to_string(); 5], true),
+            (EnvelopeSpec::No10Window, vec!["A".to_string(); 6], false),
+        ];
+
+        for (spec, adr_lnes, expected) in cases {
+            let res = layout(spec, &adr_lnes, None);
+            assert_eq!(res.is_ok(), expected, "{spec} {adr_lnes:?}");
+        }
+    }
+
+    #[test]
+    fn test_layout_windows() {
+        // The return address sits inside its window.
+        let lyt = layout(EnvelopeSpec::No10Window, &lnes("Senator"), None).unwrap();
+        let win = Windows::default();
+        assert!(lyt.from.0 > win.from.llx && lyt.from.1 < win.from.ury);
+
+        // A barcode too close to the window edge fails.
+        let cases = vec![
+            (Windows::default(), true),
+            (
+                Windows {
+                    to: Area {
+                        llx: 19.1,
+                        lly: 15.9,
+                        urx: 100.0,
+                        ury: 44.5,
+                    },
+                    ..Windows::default()
+                },
+                false,
+            ),
+        ];
+
+        for (windows, expected) in cases {
+            let res = Layout::new(
+                EnvelopeSpec::No10Window,
+                &windows,
+                &from_lnes(),
+                &lnes("Senator"),
+                2,
+                None,
+            );
+            assert_eq!(res.is_ok(), expected, "{windows:?}");
+        }
+    }
+
+    #[test]
+    fn test_layout_endorsement() {
+        let cases = vec![
+            EndorsementPos::BelowReturnAdr,
+            EndorsementPos::AboveAdr,
+            EndorsementPos::LeftOfPostage,
+            EndorsementPos::BelowPostage,
+        ];
+
+        for pos in cases {
+            let lyt = layout(
+                EnvelopeSpec::No10,
+                &lnes("Senator"),
+                Some(("Forwarding Service Requested", pos)),
+            )
+            .unwrap();
+            let (x, y) = lyt.endorsement.unwrap();
+            assert!(x > 0.0 && y > CLEAR_ZONE_HEIGHT, "{pos:?}");
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use crate::core::*;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+const FLE_PTH: &str = "serial_ledger.json";
+
+/// The number of serial IDs in the 6-digit IMb serial field.
+pub const SERIAL_CNT: u32 = 1_000_000;
+
+/// Days a serial ID must stay unique.
+///
+/// The USPS requires a mailpiece IMb to be unique for 45 days
+/// from the mailing date.
+pub const UNIQUE_DAYS: i64 = 45;
+
+/// Serial ID ranges allocated to mailings.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Ledger {
+    pub allocations: Vec<Allocation>,
+    /// Tray serial ranges.
+    ///
+    /// Tray barcodes have their own serial field, which may be as short
+    /// as 4 digits.
+    #[serde(default)]
+    pub tray_allocations: Vec<Allocation>,
+}
+
+/// A contiguous range of serial IDs allocated to a mailing.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Allocation {
+    /// The mailing name, such as `2024-Q3`.
+    pub mailing: String,
+    pub first: u32,
+    pub cnt: u32,
+    /// The mailing date, which starts the uniqueness window.
+    pub date: NaiveDate,
+}
+
+impl Allocation {
+    /// The serial ID after the last ID in the range.
+    pub fn end(&self) -> u32 {
+        self.first + self.cnt
+    }
+
+    /// Returns true when two ranges share a serial ID.
+    pub fn overlaps(&self, first: u32, cnt: u32) -> bool {
+        first < self.end() && self.first < first + cnt
+    }
+
+    /// Returns true when the range is within the uniqueness window of
+    /// a mailing on `mailing_date`, before or after it.
+    pub fn is_active(&self, mailing_date: NaiveDate) -> bool {
+        (mailing_date - self.date).num_days().abs() < UNIQUE_DAYS
+    }
+}
+
+impl Ledger {
+    /// Reads the ledger from disk.
+    ///
+    /// A missing ledger is empty.
+    pub fn read() -> Result<Ledger> {
+        if !Path::new(FLE_PTH).exists() {
+            return Ok(Ledger::default());
+        }
+        read_from_file::<Ledger>(FLE_PTH)
+    }
+
+    /// Writes the ledger to disk.
+    pub fn save(&self) -> Result<()> {
+        write_to_file(self, FLE_PTH)
+    }
+
+    /// Allocates serial IDs for a mailing and writes the ledger to disk.
+    ///
+    /// `seed` is the first serial ID of an empty ledger. The range stays
+    /// unique from `mailing_date`, not the day it is allocated.
+    /// Returns the first serial ID of the range.
+    pub fn allocate_serials(
+        mailing: &str,
+        cnt: usize,
+        seed: u32,
+        mailing_date: NaiveDate,
+    ) -> Result<u32> {
+        let mut ledger = Ledger::read()?;
+        let alloc = ledger.allocate(mailing, cnt, seed, mailing_date)?;
+        ledger.save()?;
+        eprintln!(
+            "serial IDs {:06}-{:06} for {}",
+            alloc.first,
+            alloc.end() - 1,
+            mailing
+        );
+        Ok(alloc.first)
+    }
+
+    /// Allocates tray serials for a mailing and writes the ledger to disk.
+    ///
+    /// `serial_cnt` is the number of serials in the tray barcode field.
+    /// Returns the first tray serial of the range.
+    pub fn allocate_tray_serials(
+        mailing: &str,
+        cnt: usize,
+        serial_cnt: u32,
+        mailing_date: NaiveDate,
+    ) -> Result<u32> {
+        let mut ledger = Ledger::read()?;
+        let alloc = ledger.allocate_trays(mailing, cnt, serial_cnt, mailing_date)?;
+        ledger.save()?;
+        eprintln!(
+            "tray serials {:04}-{:04} for {}",
+            alloc.first,
+            alloc.end() - 1,
+            mailing
+        );
+        Ok(alloc.first)
+    }
+
+    /// Allocates the range after the latest allocation.
+    ///
+    /// A range that would pass the last 6-digit serial ID rolls over to 0.
+    /// Fails when the range overlaps a range allocated within the
+    /// uniqueness window.
+    pub fn allocate(
+        &mut self,
+        mailing: &str,
+        cnt: usize,
+        seed: u32,
+        mailing_date: NaiveDate,
+    ) -> Result<Allocation> {
+        allocate_range(
+            &mut self.allocations,
+            mailing,
+            cnt,
+            seed,
+            SERIAL_CNT,
+            mailing_date,
+        )
+        .map_err(|err| anyhow!("serial IDs {}", err))
+    }
+
+    /// Allocates the tray range after the latest tray allocation.
+    ///
+    /// Tray serials start at 0 and roll over at `serial_cnt`.
+    pub fn allocate_trays(
+        &mut self,
+        mailing: &str,
+        cnt: usize,
+        serial_cnt: u32,
+        mailing_date: NaiveDate,
+    ) -> Result<Allocation> {
+        allocate_range(
+            &mut self.tray_allocations,
+            mailing,
+            cnt,
+            0,
+            serial_cnt,
+            mailing_date,
+        )
+        .map_err(|err| anyhow!("tray serials {}", err))
+    }
+
+    /// Returns pairs of active allocations that share serial IDs.
+    ///
+    /// Tray allocations are compared with each other.
+    pub fn overlaps(&self, mailing_date: NaiveDate) -> Vec<(&Allocation, &Allocation)> {
+        let mut ret = overlaps(&self.allocations, mailing_date);
+        ret.extend(overlaps(&self.tray_allocations, mailing_date));
+        ret
+    }
+}
+
+/// Allocates a range after the latest allocation, rolling over at
+/// `serial_cnt`.
+fn allocate_range(
+    allocs: &mut Vec<Allocation>,
+    mailing: &str,
+    cnt: usize,
+    seed: u32,
+    serial_cnt: u32,
+    mailing_date: NaiveDate,
+) -> Result<Allocation> {
+    let cnt = u32::try_from(cnt)
+        .ok()
+        .filter(|cnt| *cnt > 0 && *cnt <= serial_cnt)
+        .ok_or(anyhow!("unable to allocate {}", cnt))?;
+
+    // Start after the latest allocation.
+    let mut first = allocs.last().map_or(seed, |o| o.end()) % serial_cnt;
+    if first + cnt > serial_cnt {
+        // Roll over to keep the range contiguous.
+        first = 0;
+    }
+
+    // Check uniqueness window.
+    if let Some(prev) = allocs
+        .iter()
+        .filter(|o| o.is_active(mailing_date))
+        .find(|o| o.overlaps(first, cnt))
+    {
+        return Err(anyhow!(
+            "{:06}-{:06} overlap {} mailed {}",
+            first,
+            first + cnt - 1,
+            prev.mailing,
+            prev.date
+        ));
+    }
+
+    let alloc = Allocation {
+        mailing: mailing.into(),
+        first,
+        cnt,
+        date: mailing_date,
+    };
+    allocs.push(alloc.clone());
+    Ok(alloc)
+}
+
+/// Returns pairs of active allocations that share serial IDs.
+fn overlaps(allocs: &[Allocation], mailing_date: NaiveDate) -> Vec<(&Allocation, &Allocation)> {
+    let active: Vec<&Allocation> = allocs
+        .iter()
+        .filter(|o| o.is_active(mailing_date))
+        .collect();
+    let mut ret = Vec::new();
+    for (idx, a) in active.iter().enumerate() {
+        for b in active.iter().skip(idx + 1) {
+            if a.overlaps(b.first, b.cnt) {
+                ret.push((*a, *b));
+            }
+        }
+    }
+    ret
+}
+
+impl fmt::Display for Ledger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let allocs = self
+            .allocations
+            .iter()
+            .map(|o| ("", o))
+            .chain(self.tray_allocations.iter().map(|o| ("tray ", o)));
+        for (idx, (kind, alloc)) in allocs.enumerate() {
+            if idx != 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{} {}{:06}-{:06} {}",
+                alloc.date,
+                kind,
+                alloc.first,
+                alloc.end() - 1,
+                alloc.mailing
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    #[test]
+    fn test_allocate_sequential() {
+        let mut ledger = Ledger::default();
+        let today = date(2024, 7, 1);
+
+        // Empty ledger starts at the seed.
+        let a = ledger.allocate("2024-Q3", 100, 501, today).unwrap();
+        assert_eq!((a.first, a.cnt), (501, 100));
+
+        // Next range follows the last range.
+        let b = ledger.allocate("2024-Q3", 50, 501, today).unwrap();
+        assert_eq!(b.first, 601);
+        assert!(ledger.overlaps(today).is_empty());
+    }
+
+    #[test]
+    fn test_allocate_rollover() {
+        let mut ledger = Ledger::default();
+        let today = date(2024, 7, 1);
+        let a = ledger.allocate("2024-Q3", 100, 999_850, today).unwrap();
+        assert_eq!(a.first, 999_850);
+
+        // Range past the last serial ID rolls over to 0.
+        let b = ledger.allocate("2024-Q3", 100, 0, today).unwrap();
+        assert_eq!(b.first, 0);
+        let c = ledger.allocate("2024-Q3", 100, 0, today).unwrap();
+        assert_eq!(c.first, 100);
+        assert!(ledger.overlaps(today).is_empty());
+    }
+
+    #[test]
+    fn test_allocate_overlap() {
+        let mut ledger = Ledger::default();
+        ledger
+            .allocate("2024-Q2", 100, 0, date(2024, 6, 1))
+            .unwrap();
+        ledger
+            .allocate("2024-Q3", 999_800, 0, date(2024, 6, 2))
+            .unwrap();
+
+        // Rolls over into a range used within 45 days.
+        assert!(ledger
+            .allocate("2024-Q3", 200, 0, date(2024, 6, 3))
+            .is_err());
+
+        // Range is free after 45 days.
+        let c = ledger
+            .allocate("2024-Q3", 200, 0, date(2024, 7, 17))
+            .unwrap();
+        assert_eq!(c.first, 0);
+
+        // A range for a later mailing is active for earlier mailings
+        // within 45 days.
+        let mut ledger = Ledger::default();
+        ledger
+            .allocate("2024-Q3", SERIAL_CNT as usize, 0, date(2024, 8, 20))
+            .unwrap();
+        assert!(ledger
+            .allocate("2024-Q3", 100, 0, date(2024, 8, 1))
+            .is_err());
+        assert!(ledger.allocate("2024-Q2", 100, 0, date(2024, 6, 1)).is_ok());
+    }
+
+    #[test]
+    fn test_allocate_invalid() {
+        let mut ledger = Ledger::default();
+        let today = date(2024, 7, 1);
+        assert!(ledger.allocate("2024-Q3", 0, 0, today).is_err());
+        assert!(ledger
+            .allocate("2024-Q3", SERIAL_CNT as usize + 1, 0, today)
+            .is_err());
+        assert!(ledger.allocations.is_empty());
+    }
+
+    #[test]
+    fn test_allocate_trays() {
+        let mut ledger = Ledger::default();
+        let today = date(2024, 7, 1);
+
+        // Mailpiece serial IDs past 4 digits leave tray serials unchanged.
+        let a = ledger.allocate("2024-Q3", 20, 10_000, today).unwrap();
+        assert_eq!(a.first, 10_000);
+        let b = ledger.allocate_trays("2024-Q3", 3, 10_000, today).unwrap();
+        assert_eq!((b.first, b.cnt), (0, 3));
+        let c = ledger.allocate_trays("2024-Q3", 3, 10_000, today).unwrap();
+        assert_eq!(c.first, 3);
+
+        // 4-digit tray serials roll over within the uniqueness window.
+        assert!(ledger
+            .allocate_trays("2024-Q3", 9_995, 10_000, today)
+            .is_err());
+        assert!(ledger
+            .allocate_trays("2024-Q3", 9_995, 10_000, date(2024, 8, 15))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let today = date(2024, 7, 1);
+        let alloc = |first, cnt, date| Allocation {
+            mailing: "2024-Q3".into(),
+            first,
+            cnt,
+            date,
+        };
+        let ledger = Ledger {
+            allocations: vec![
+                alloc(0, 100, today),
+                alloc(99, 10, today),
+                alloc(200, 10, today),
+                // Outside window.
+                alloc(0, 300, date(2024, 1, 1)),
+            ],
+            // Tray serials are separate from mailpiece serial IDs.
+            tray_allocations: vec![alloc(0, 5, today)],
+        };
+        let overlaps = ledger.overlaps(today);
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].1.first, 99);
+    }
+}
@@ -0,0 +1,354 @@
+use crate::honorific::*;
+use crate::models::*;
+use anyhow::{anyhow, Result};
+use heck::ToTitleCase;
+
+/// Merge fields available in letter templates.
+pub const MERGE_FIELDS: [&str; 8] = [
+    "name",
+    "title1",
+    "title2",
+    "org",
+    "role",
+    "city",
+    "salutation",
+    "inside_address",
+];
+
+/// A part of a letter template.
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    /// A merge field, such as `{{title1}}`.
+    Field(String),
+    /// A block included for recipients with one of the roles, such as
+    /// `{{#role Military Scientific}}...{{/role}}`.
+    Role(Vec<Role>, Vec<Part>),
+    /// A block included when a field is filled, such as
+    /// `{{#title2}}...{{/title2}}`.
+    Filled(String, Vec<Part>),
+}
+
+/// An open block while parsing.
+#[derive(Debug, Default)]
+struct Block {
+    key: String,
+    roles: Option<Vec<Role>>,
+    parts: Vec<Part>,
+}
+
+/// A parsed letter template paragraph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tmpl(Vec<Part>);
+
+impl Tmpl {
+    /// Parses template text.
+    ///
+    /// Fails on an unknown field or role, or an unclosed block.
+    pub fn parse(txt: &str) -> Result<Tmpl> {
+        let mut stk = vec![Block::default()];
+        let mut rest = txt;
+        while let Some(idx) = rest.find("{{") {
+            if idx > 0 {
+                stk.last_mut()
+                    .unwrap()
+                    .parts
+                    .push(Part::Text(rest[..idx].into()));
+            }
+            let end = rest[idx..]
+                .find("}}")
+                .ok_or(anyhow!("unclosed placeholder in {:?}", txt))?;
+            let tag = rest[idx + 2..idx + end].trim();
+            rest = &rest[idx + end + 2..];
+
+            if let Some(tag) = tag.strip_prefix('#') {
+                // Open a block.
+                let mut words = tag.split_whitespace();
+                let key = words.next().unwrap_or_default().to_string();
+                let roles = if key == "role" {
+                    let roles = words.map(parse_role).collect::<Result<Vec<_>>>()?;
+                    if roles.is_empty() {
+                        return Err(anyhow!("missing role in {{{{#role}}}} block"));
+                    }
+                    Some(roles)
+                } else {
+                    check_field(&key)?;
+                    None
+                };
+                stk.push(Block {
+                    key,
+                    roles,
+                    parts: Vec::new(),
+                });
+            } else if let Some(tag) = tag.strip_prefix('/') {
+                // Close a block.
+                if stk.len() == 1 || stk.last().unwrap().key != tag {
+                    return Err(anyhow!("unexpected {{{{/{}}}}} in {:?}", tag, txt));
+                }
+                let blk = stk.pop().unwrap();
+                let part = match blk.roles {
+                    Some(roles) => Part::Role(roles, blk.parts),
+                    None => Part::Filled(blk.key, blk.parts),
+                };
+                stk.last_mut().unwrap().parts.push(part);
+            } else {
+                check_field(tag)?;
+                stk.last_mut().unwrap().parts.push(Part::Field(tag.into()));
+            }
+        }
+        if !rest.is_empty() {
+            stk.last_mut().unwrap().parts.push(Part::Text(rest.into()));
+        }
+        if stk.len() != 1 {
+            return Err(anyhow!(
+                "unclosed {{{{#{}}}}} block in {:?}",
+                stk.last().unwrap().key,
+                txt
+            ));
+        }
+        Ok(Tmpl(stk.pop().unwrap().parts))
+    }
+
+    /// Renders the template for a recipient.
+    ///
+    /// Fails on a field without a value outside a `{{#field}}` block.
+    pub fn render(&self, fields: &MergeFields) -> Result<String> {
+        let mut ret = String::new();
+        render_parts(&self.0, fields, &mut ret)?;
+        Ok(ret)
+    }
+}
+
+fn render_parts(parts: &[Part], fields: &MergeFields, ret: &mut String) -> Result<()> {
+    for part in parts {
+        match part {
+            Part::Text(txt) => ret.push_str(txt),
+            Part::Field(key) => {
+                let val = fields.get(key).ok_or(anyhow!(
+                    "unfilled {{{{{}}}}} for {}",
+                    key,
+                    fields.name
+                ))?;
+                ret.push_str(&val);
+            }
+            Part::Role(roles, parts) => {
+                if fields.role.as_ref().is_some_and(|o| roles.contains(o)) {
+                    render_parts(parts, fields, ret)?;
+                }
+            }
+            Part::Filled(key, parts) => {
+                if fields.get(key).is_some() {
+                    render_parts(parts, fields, ret)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_field(key: &str) -> Result<()> {
+    if !MERGE_FIELDS.contains(&key) {
+        return Err(anyhow!("unknown placeholder {{{{{}}}}}", key));
+    }
+    Ok(())
+}
+
+fn parse_role(txt: &str) -> Result<Role> {
+    [
+        Role::Military,
+        Role::Scientific,
+        Role::Political,
+        Role::Observer,
+    ]
+    .into_iter()
+    .find(|o| o.to_string().eq_ignore_ascii_case(txt))
+    .ok_or(anyhow!("unknown role {:?}", txt))
+}
+
+/// Merge field values for one recipient.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MergeFields {
+    pub name: String,
+    pub title1: Option<String>,
+    pub title2: Option<String>,
+    pub org: Option<String>,
+    pub role: Option<Role>,
+    pub city: String,
+    pub salutation: String,
+    /// Inside address lines, one per line of the paragraph.
+    pub inside_address: Vec<String>,
+}
+
+impl MergeFields {
+    pub fn new(mp: &Mailpiece) -> MergeFields {
+        let frm = FormOfAddress::new(mp);
+        MergeFields {
+            name: mp.name.clone(),
+            title1: mp.title1.clone(),
+            title2: mp.title2.clone(),
+            org: mp.org.clone(),
+            role: mp.role.clone(),
+            city: mp.city.to_title_case(),
+            salutation: frm.salutation,
+            inside_address: frm.inside_address,
+        }
+    }
+
+    /// The value of a field, or `None` when unfilled.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let val = match key {
+            "name" => Some(self.name.clone()),
+            "title1" => self.title1.clone(),
+            "title2" => self.title2.clone(),
+            "org" => self.org.clone(),
+            "role" => self.role.as_ref().map(|o| o.to_string()),
+            "city" => Some(self.city.clone()),
+            "salutation" => Some(self.salutation.clone()),
+            "inside_address" => Some(self.inside_address.join("\n")),
+            _ => None,
+        };
+        val.filter(|o| !o.is_empty())
+    }
+}
+
+impl Letter {
+    /// Merges template paragraphs for a recipient.
+    ///
+    /// Paragraphs match the template paragraphs one to one. A paragraph
+    /// whose conditional blocks leave it blank is empty.
+    pub fn merge(tmpls: &[Tmpl], fields: &MergeFields, from: &str) -> Result<Letter> {
+        let paragraphs = tmpls
+            .iter()
+            .map(|tmpl| {
+                tmpl.render(fields).map(|txt| {
+                    if txt.trim().is_empty() {
+                        String::new()
+                    } else {
+                        txt
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Letter {
+            to: fields.name.clone(),
+            paragraphs,
+            from: from.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(role: Option<Role>, title2: Option<&str>) -> MergeFields {
+        MergeFields {
+            name: "Jane Doe".into(),
+            title1: Some("Senator".into()),
+            title2: title2.map(String::from),
+            org: Some("U.S. Senate".into()),
+            role,
+            city: "Washington".into(),
+            salutation: "Dear Jane Doe:".into(),
+            inside_address: vec!["Jane Doe".into(), "Washington, DC 20510".into()],
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let cases =
+            vec![
+            ("{{salutation}}", fields(None, None), "Dear Jane Doe:"),
+            (
+                "{{name}}, {{title1}} of {{org}} in {{city}}",
+                fields(None, None),
+                "Jane Doe, Senator of U.S. Senate in Washington",
+            ),
+            (
+                "A{{#role Military}} salute{{/role}}{{#role Political Scientific}} vote{{/role}}",
+                fields(Some(Role::Political), None),
+                "A vote",
+            ),
+            (
+                "{{#role military}}Thank you for your service.{{/role}}",
+                fields(Some(Role::Military), None),
+                "Thank you for your service.",
+            ),
+            (
+                "{{#role Military}}Thank you for your service.{{/role}}",
+                fields(None, None),
+                "",
+            ),
+            (
+                "{{title1}}{{#title2}}, {{title2}}{{/title2}}",
+                fields(None, Some("Armed Services")),
+                "Senator, Armed Services",
+            ),
+            (
+                "{{title1}}{{#title2}}, {{title2}}{{/title2}}",
+                fields(None, None),
+                "Senator",
+            ),
+            (
+                "{{#role Political}}{{#title2}}{{title2}}{{/title2}}{{/role}}",
+                fields(Some(Role::Political), Some("Ethics")),
+                "Ethics",
+            ),
+            ("{{ role }}", fields(Some(Role::Scientific), None), "Scientific"),
+            (
+                "{{inside_address}}",
+                fields(None, None),
+                "Jane Doe\nWashington, DC 20510",
+            ),
+        ];
+
+        for (txt, fields, expected) in cases {
+            let tmpl = Tmpl::parse(txt).unwrap();
+            assert_eq!(tmpl.render(&fields).unwrap(), expected, "{txt}");
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let cases = vec![
+            "{{nickname}}",
+            "{{name",
+            "{{#role Navy}}x{{/role}}",
+            "{{#role}}x{{/role}}",
+            "{{#role Military}}x",
+            "x{{/role}}",
+            "{{#title2}}x{{/title1}}",
+        ];
+
+        for txt in cases {
+            assert!(Tmpl::parse(txt).is_err(), "{txt}");
+        }
+    }
+
+    #[test]
+    fn test_render_unfilled() {
+        let cases = vec!["{{title2}}", "{{role}}"];
+
+        for txt in cases {
+            let tmpl = Tmpl::parse(txt).unwrap();
+            assert!(tmpl.render(&fields(None, None)).is_err(), "{txt}");
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let tmpls: Vec<Tmpl> = [
+            "{{salutation}}",
+            "{{#role Military}}Thank you for your service.{{/role}}",
+            "Sincerely,",
+        ]
+        .iter()
+        .map(|o| Tmpl::parse(o).unwrap())
+        .collect();
+
+        let ltr = Letter::merge(&tmpls, &fields(Some(Role::Political), None), "Acme").unwrap();
+        assert_eq!(ltr.to, "Jane Doe");
+        assert_eq!(ltr.from, "Acme");
+        assert_eq!(ltr.paragraphs, vec!["Dear Jane Doe:", "", "Sincerely,"]);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::core::*;
+use crate::mailing::*;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Directory of labeling list files.
+pub const DIR_LLISTS: &str = "labeling_lists";
+
+/// Days after its effective date that a labeling list is flagged as stale.
+///
+/// The USPS revises labeling lists several times a year.
+pub const STALE_DAYS: i64 = 180;
+
+/// A tray destination from a labeling list.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Label {
+    /// The destination ZIP Code or ZIP Code prefix, such as `205`.
+    pub zip: String,
+    /// The destination line text, such as `WASHINGTON DC 205`.
+    pub name: String,
+}
+
+/// A USPS labeling list mapping 3-digit ZIP Code prefixes to tray destinations.
+///
+/// See https://pe.usps.com/text/LabelingLists/welcome.htm.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LabelingList {
+    pub sort_lvl: SortLvl,
+    /// The date the list takes effect.
+    #[serde(default)]
+    pub effective: Option<NaiveDate>,
+    /// Destinations keyed by 3-digit ZIP Code prefix, such as `205`.
+    pub labels: BTreeMap<String, Label>,
+}
+
+/// Labeling lists keyed by sort level.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LabelingLists(pub BTreeMap<SortLvl, LabelingList>);
+
+impl LabelingList {
+    /// Parses a labeling list copied from the USPS as text.
+    ///
+    /// Each entry line holds ZIP Code prefixes and the label destination,
+    /// ending with the destination ZIP Code:
+    ///
+    /// ```text
+    /// L801 AADCs - Letter-Size Mailings
+    /// Effective July 14, 2024
+    /// 005, 115, 117-119    AADC MID-ISLAND NY 117
+    /// ```
+    ///
+    /// Blank lines and lines starting with `#` are skipped.
+    pub fn parse(sort_lvl: SortLvl, txt: &str) -> Result<LabelingList> {
+        let mut ret = LabelingList {
+            sort_lvl,
+            effective: None,
+            labels: BTreeMap::new(),
+        };
+        for (idx, lne) in txt.lines().enumerate() {
+            let lne = lne.trim();
+            if lne.is_empty() || lne.starts_with('#') {
+                continue;
+            }
+            let err =
+                |msg: &str| anyhow!("{} line {}: {}: {:?}", sort_lvl.code(), idx + 1, msg, lne);
+
+            // Effective date.
+            if lne.to_lowercase().starts_with("effective") {
+                let date = lne["effective".len()..].trim_start_matches(':').trim();
+                ret.effective = Some(parse_date(date).ok_or(err("invalid effective date"))?);
+                continue;
+            }
+
+            // Title.
+            if !lne.starts_with(|c: char| c.is_ascii_digit()) {
+                continue;
+            }
+
+            // Entry.
+            let (pfxs, label) = split_entry(lne).ok_or(err("missing label"))?;
+            let zip = label
+                .split_whitespace()
+                .last()
+                .filter(|zip| {
+                    (zip.len() == 3 || zip.len() == 5) && zip.chars().all(|c| c.is_ascii_digit())
+                })
+                .ok_or(err("missing label ZIP Code"))?;
+            let label = Label {
+                zip: zip.into(),
+                name: label.into(),
+            };
+            for pfx in parse_prefixes(pfxs).ok_or(err("invalid ZIP Code prefixes"))? {
+                ret.labels.insert(pfx, label.clone());
+            }
+        }
+        if ret.labels.is_empty() {
+            return Err(anyhow!("{} has no entries", sort_lvl.code()));
+        }
+        Ok(ret)
+    }
+
+    /// Describes a problem with the list for a mailing date.
+    ///
+    /// A list without an effective date, or older than `STALE_DAYS`, is stale.
+    pub fn check(&self, mailing_date: NaiveDate) -> LlistStatus {
+        match self.effective {
+            None => LlistStatus::Undated,
+            Some(effective) if effective > mailing_date => LlistStatus::NotEffective(effective),
+            Some(effective) if (mailing_date - effective).num_days() > STALE_DAYS => {
+                LlistStatus::Stale(effective)
+            }
+            Some(effective) => LlistStatus::Current(effective),
+        }
+    }
+}
+
+/// The state of a labeling list on a mailing date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlistStatus {
+    Current(NaiveDate),
+    Stale(NaiveDate),
+    NotEffective(NaiveDate),
+    Undated,
+}
+
+impl fmt::Display for LlistStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LlistStatus::Current(date) => write!(f, "effective {}", date),
+            LlistStatus::Stale(date) => write!(f, "stale, effective {}", date),
+            LlistStatus::NotEffective(date) => write!(f, "not effective until {}", date),
+            LlistStatus::Undated => write!(f, "stale, no effective date"),
+        }
+    }
+}
+
+impl LabelingLists {
+    /// Loads each labeling list from a directory.
+    ///
+    /// Reads `<code>.txt` copied from the USPS, such as `L801A.txt`,
+    /// or else `<code>.json`. Missing lists are skipped; the presort then
+    /// uses fewer tray levels.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<LabelingLists> {
+        let mut ret = LabelingLists::default();
+        for sort_lvl in SortLvl::lists() {
+            let pth_txt = dir.as_ref().join(format!("{}.txt", sort_lvl.code()));
+            let pth_json = dir.as_ref().join(format!("{}.json", sort_lvl.code()));
+            let list = if pth_txt.exists() {
+                eprintln!("Reading file: {:?}", pth_txt);
+                LabelingList::parse(sort_lvl, &fs::read_to_string(&pth_txt)?)?
+            } else if pth_json.exists() {
+                let list = read_from_file::<LabelingList>(&pth_json.to_string_lossy())?;
+                if list.sort_lvl != sort_lvl {
+                    return Err(anyhow!(
+                        "{:?}: expected {:?} list, found {:?}",
+                        pth_json,
+                        sort_lvl,
+                        list.sort_lvl
+                    ));
+                }
+                list
+            } else {
+                continue;
+            };
+            ret.insert(list);
+        }
+        Ok(ret)
+    }
+
+    pub fn insert(&mut self, list: LabelingList) {
+        self.0.insert(list.sort_lvl, list);
+    }
+
+    /// Finds the destination for a 5-digit ZIP Code in a sort level's list.
+    pub fn label(&self, sort_lvl: SortLvl, zip5: u32) -> Option<&Label> {
+        self.0
+            .get(&sort_lvl)
+            .and_then(|list| list.labels.get(&format!("{:03}", zip5 / 100)))
+    }
+
+    /// Checks each list against a mailing date.
+    ///
+    /// Prints a warning for stale lists and fails when a list is
+    /// not yet effective.
+    pub fn validate(&self, mailing_date: NaiveDate) -> Result<()> {
+        for list in self.0.values() {
+            match list.check(mailing_date) {
+                LlistStatus::Current(_) => {}
+                LlistStatus::NotEffective(_) => {
+                    return Err(anyhow!(
+                        "labeling list {} is {}",
+                        list.sort_lvl.code(),
+                        list.check(mailing_date)
+                    ));
+                }
+                status => eprintln!(
+                    "warning: labeling list {} is {}",
+                    list.sort_lvl.code(),
+                    status
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits an entry line into ZIP Code prefixes and label.
+///
+/// Splits at a tab, or else at the first word that is not a prefix.
+fn split_entry(lne: &str) -> Option<(&str, &str)> {
+    if let Some((pfxs, label)) = lne.split_once('\t') {
+        return Some((pfxs.trim(), label.trim()));
+    }
+    let idx =
+        lne.find(|c: char| !(c.is_ascii_digit() || c == ',' || c == '-' || c.is_whitespace()))?;
+    Some((lne[..idx].trim(), lne[idx..].trim()))
+}
+
+/// Parses ZIP Code prefixes and ranges, such as `005, 117-119`.
+fn parse_prefixes(pfxs: &str) -> Option<Vec<String>> {
+    let pfx = |s: &str| {
+        let s = s.trim();
+        if s.len() == 3 {
+            s.parse::<u16>().ok()
+        } else {
+            None
+        }
+    };
+    let mut ret = Vec::new();
+    for part in pfxs.split(',').filter(|part| !part.trim().is_empty()) {
+        let (fst, lst) = match part.split_once('-') {
+            Some((fst, lst)) => (pfx(fst)?, pfx(lst)?),
+            None => (pfx(part)?, pfx(part)?),
+        };
+        if fst > lst {
+            return None;
+        }
+        ret.extend((fst..=lst).map(|o| format!("{:03}", o)));
+    }
+    if ret.is_empty() {
+        None
+    } else {
+        Some(ret)
+    }
+}
+
+/// Parses a date such as `July 14, 2024`, `2024-07-14` or `07/14/2024`.
+pub fn parse_date(s: &str) -> Option<NaiveDate> {
+    ["%B %d, %Y", "%b %d, %Y", "%Y-%m-%d", "%m/%d/%Y"]
+        .iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(s.trim(), fmt).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    #[test]
+    fn test_label() {
+        let mut lists = LabelingLists::default();
+        let mut labels = BTreeMap::new();
+        labels.insert(
+            "205".into(),
+            Label {
+                zip: "205".into(),
+                name: "WASHINGTON DC 205".into(),
+            },
+        );
+        lists.insert(LabelingList {
+            sort_lvl: SortLvl::ThreeDigitColA,
+            effective: None,
+            labels,
+        });
+
+        let label = lists.label(SortLvl::ThreeDigitColA, 20510).unwrap();
+        assert_eq!(label.name, "WASHINGTON DC 205");
+        assert!(lists.label(SortLvl::ThreeDigitColA, 20001).is_none());
+        assert!(lists
+            .label(SortLvl::AADCLetterSizeMailingsA, 20510)
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse() {
+        let txt = "\
+L801 AADCs - Letter-Size Mailings
+Effective July 14, 2024
+
+# Comment
+005, 115, 117-119\tAADC MID-ISLAND NY 117
+200-205 AADC WASHINGTON DC 200
+";
+        let list = LabelingList::parse(SortLvl::AADCLetterSizeMailingsA, txt).unwrap();
+        assert_eq!(list.effective, Some(date(2024, 7, 14)));
+        let pfxs: Vec<&str> = list.labels.keys().map(|o| o.as_str()).collect();
+        assert_eq!(
+            pfxs,
+            vec!["005", "115", "117", "118", "119", "200", "201", "202", "203", "204", "205"]
+        );
+        let label = &list.labels["203"];
+        assert_eq!(label.zip, "200");
+        assert_eq!(label.name, "AADC WASHINGTON DC 200");
+        assert_eq!(list.labels["005"].name, "AADC MID-ISLAND NY 117");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let cases = vec![
+            "",
+            "Effective someday\n005 AADC NY 117",
+            "005",
+            "005 AADC MID-ISLAND NY",
+            "05 AADC MID-ISLAND NY 117",
+            "119-117 AADC MID-ISLAND NY 117",
+        ];
+
+        for txt in cases {
+            assert!(
+                LabelingList::parse(SortLvl::AADCLetterSizeMailingsA, txt).is_err(),
+                "{txt}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut list = LabelingList {
+            sort_lvl: SortLvl::ThreeDigitColA,
+            effective: None,
+            labels: BTreeMap::new(),
+        };
+        let mailing_date = date(2024, 7, 1);
+        assert_eq!(list.check(mailing_date), LlistStatus::Undated);
+
+        let cases = vec![
+            (
+                date(2024, 7, 2),
+                LlistStatus::NotEffective(date(2024, 7, 2)),
+            ),
+            (date(2024, 7, 1), LlistStatus::Current(date(2024, 7, 1))),
+            (date(2024, 1, 3), LlistStatus::Current(date(2024, 1, 3))),
+            (date(2023, 12, 1), LlistStatus::Stale(date(2023, 12, 1))),
+        ];
+        for (effective, expected) in cases {
+            list.effective = Some(effective);
+            assert_eq!(list.check(mailing_date), expected);
+        }
+    }
+}
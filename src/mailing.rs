@@ -1,37 +1,49 @@
+use crate::adr_policy::*;
+use crate::archive::*;
 use crate::core::*;
+use crate::dedup::*;
 use crate::envelope::*;
+use crate::field_map::*;
+use crate::imtb::*;
+use crate::layout::*;
+use crate::ledger::*;
+use crate::letter::*;
+use crate::llist::*;
 use crate::models::*;
 use crate::postage_statement::*;
 use crate::prsr::*;
+use crate::rates::*;
+use crate::tray_label::*;
 use crate::usps::*;
+use crate::window::*;
 use anyhow::{anyhow, Result};
 use chrono::Local;
 use chrono::NaiveDate;
 use itertools::*;
 use pdf_doc::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fs;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Cursor};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+use strum::{EnumIter, IntoEnumIterator};
 use TraySize::*;
 
 const FLE_PTH: &str = "mailing.json";
 const FLE_PTH_CFG: &str = "mailing_cfg.json";
 const FLE_PTH_LTR: &str = "letter-template.json";
 
-const PRC_FIVE_DIG: f64 = 0.173; // PS Form 3602-N
-const PRC_MIXED_AADC: f64 = 0.208; // PS Form 3602-N
-
 lazy_static! {
     /// A mailing configuration.
-    pub static ref CFG: MailingCfg = read_from_file::<MailingCfg>(FLE_PTH_CFG).unwrap();
+    pub static ref CFG: MailingCfg = mailing_cfg().unwrap();
 }
 
-// TODO: ADD "Return Service Requested" TO ENVELOPE.
+/// A mailing configuration path set from the command line.
+static CFG_PTH: OnceLock<String> = OnceLock::new();
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Mailing {
@@ -45,10 +57,22 @@ pub struct Mailing {
     pub tray_1ft_cnt: u8,
     pub tray_2ft_cnt: u8,
     pub five_dig_cnt: u16,
+    /// Pieces in 3-digit and AADC trays, which share the AADC price.
+    #[serde(default)]
+    pub aadc_cnt: u16,
     pub mixed_aadc_cnt: u16,
     pub postage_subtotal_five_dig: f64,
+    #[serde(default)]
+    pub postage_subtotal_aadc: f64,
     pub postage_subtotal_mixed_aadc: f64,
     pub part_a_subtotal: f64,
+    /// The rate table used for postage, such as
+    /// `Marketing Mail Nonprofit letters effective 2024-07-14`.
+    #[serde(default)]
+    pub rate_table: String,
+    /// Postage by rate category.
+    #[serde(default)]
+    pub postage_lines: Vec<PostageLine>,
 }
 
 impl Mailing {
@@ -61,112 +85,24 @@ impl Mailing {
             tray_1ft_cnt: 0,
             tray_2ft_cnt: 0,
             five_dig_cnt: 0,
+            aadc_cnt: 0,
             mixed_aadc_cnt: 0,
             postage_subtotal_five_dig: 0.0,
+            postage_subtotal_aadc: 0.0,
             postage_subtotal_mixed_aadc: 0.0,
             part_a_subtotal: 0.0,
+            rate_table: String::new(),
+            postage_lines: Vec::new(),
         }
     }
 
     pub async fn load(pers: &mut [Person]) -> Result<Mailing> {
         // Read file from disk.
-        let mut mailing = match read_from_file::<Mailing>(FLE_PTH) {
+        let mut mailing = match Mailing::read() {
             Ok(mailing_from_disk) => mailing_from_disk,
-            Err(_) => {
-                let mut mailing = Mailing::new();
-
-                // Create mailpieces for each person.
-                let adr_cnt = pers.iter().map(|p| p.adr_len()).sum::<usize>();
-                let mut mailpieces = Vec::with_capacity(adr_cnt);
-                for per in pers.iter() {
-                    if let Some(adrs) = &per.adrs {
-                        for adr in adrs {
-                            // See guidelines.
-                            // https://about.usps.com/publications/pub28/28c2_007.htm
-                            let mp = Mailpiece {
-                                name: per.name.clone(),
-                                title1: string_to_opt(per.title1.clone()),
-                                title2: string_to_opt(per.title2.clone()),
-                                address1: adr.address1.clone(),
-                                city: adr.city.clone(),
-                                state: adr.state.clone(),
-                                zip5: adr.zip5,
-                                zip4: adr.zip4,
-                                delivery_point: adr.delivery_point.clone(),
-                                ..Default::default()
-                            };
-                            mailpieces.push(mp);
-                        }
-                    } else {
-                        return Err(anyhow!("missing address for {}", per));
-                    }
-                }
-
-                // Set mailpiece count.
-                mailing.mailpiece_cnt = mailpieces.len() as u16;
-
-                // Sort by zip code for id generation.
-                mailpieces.sort_unstable_by_key(|o| format!("{:05}{:04}", o.zip5, o.zip4));
-
-                // Calculate current id based on the previous mailing
-                // and current mailing. Each envelope gets a unique id.
-                // Id is used in the barcode.
-                let mut base_id = CFG.last_mailpiece_id + 1;
-                for (idx, mp) in mailpieces.iter_mut().enumerate() {
-                    mp.id = base_id + idx as u32;
-                }
-
-                // Pre-sort for USPS discount.
-                mailing.trays = presort_mailpieces(mailpieces);
-                eprintln!("{} trays", mailing.trays.len());
-
-                // Determine tray counts.
-                mailing.tray_1ft_cnt = mailing
-                    .trays
-                    .iter()
-                    .filter(|o| o.size == TraySize::OneFoot)
-                    .count() as u8;
-                mailing.tray_2ft_cnt = mailing
-                    .trays
-                    .iter()
-                    .filter(|o| o.size == TraySize::TwoFoot)
-                    .count() as u8;
-
-                // Determine price categories.
-                mailing.five_dig_cnt = mailing
-                    .trays
-                    .iter()
-                    .filter(|o| o.barcode_id == BarcodeId::FiveDigit)
-                    .map(|o| o.mailpieces.len())
-                    .sum::<usize>() as u16;
-                mailing.mixed_aadc_cnt = mailing
-                    .trays
-                    .iter()
-                    .filter(|o| o.barcode_id == BarcodeId::MixedAadc)
-                    .map(|o| o.mailpieces.len())
-                    .sum::<usize>() as u16;
-
-                // Calculate prices.
-                mailing.postage_subtotal_five_dig = mailing.five_dig_cnt as f64 * PRC_FIVE_DIG;
-                mailing.postage_subtotal_mixed_aadc = mailing.mixed_aadc_cnt as f64 * PRC_MIXED_AADC;
-                mailing.part_a_subtotal = mailing.postage_subtotal_five_dig + mailing.postage_subtotal_mixed_aadc;
-
-                // Write file to disk.
-                write_to_file(&mailing, FLE_PTH)?;
-
-                mailing
-            }
+            Err(_) => Mailing::presort(pers)?,
         };
 
-        // Create the directory and any necessary parent directories
-        let mut pth = PathBuf::from("mailings");
-        pth.push(&mailing.name);
-        if pth.exists() {
-            // Delete any previous directory.
-            fs::remove_dir_all(&pth)?;
-        }
-        fs::create_dir_all(&pth)?;
-
         // // Find longest title1.
         // pers.sort_unstable_by_key(|k| k.title1.len());
         // eprintln!("title1:{}", pers[pers.len() - 1].title1);
@@ -175,18 +111,201 @@ impl Mailing {
         // mailpieces.sort_unstable_by_key(|k| k.address1.len());
         // eprintln!("address1:{}", mailpieces[mailpieces.len() - 1].address1);
 
-        let mps_len = mailing
+        // Add barcodes to mailpieces.
+        mailing.add_barcodes()?;
+
+        // Create envelopes and letters.
+        mailing.create_envelopes_letters()?;
+
+        // Create tray labels.
+        mailing.create_tray_labels()?;
+
+        // Fill in postage statement pdf.
+        mailing.fill_postage_statement().await?;
+
+        // Archive the mailing as a new revision.
+        archive_mailing(&mailing)?;
+
+        // eprintln!("{} mailpieces", mailing.mailpieces.len());
+
+        Ok(mailing)
+    }
+
+    /// Reads a mailing from disk.
+    pub fn read() -> Result<Mailing> {
+        read_from_file::<Mailing>(FLE_PTH)
+    }
+
+    /// Chooses addresses and creates mailpieces for each person, pre-sorts
+    /// them into trays, and writes the mailing to disk.
+    pub fn presort(pers: &[Person]) -> Result<Mailing> {
+        let mut mailing = Mailing::new();
+
+        // Choose addresses for each person.
+        let (pers, report) = select_adrs(pers, &CFG.adr_policies, &CFG.adr_overrides);
+        eprintln!("{report}");
+        report.save()?;
+
+        // Create mailpieces for each person.
+        let adr_cnt = pers.iter().map(|p| p.adr_len()).sum::<usize>();
+        let mut mailpieces = Vec::with_capacity(adr_cnt);
+        for per in pers.iter() {
+            if let Some(adrs) = &per.adrs {
+                for adr in adrs {
+                    // See guidelines.
+                    // https://about.usps.com/publications/pub28/28c2_007.htm
+                    let mp = Mailpiece {
+                        name: per.name.clone(),
+                        name_parts: per.name_parts(),
+                        title1: string_to_opt(per.title1.clone()),
+                        title2: string_to_opt(per.title2.clone()),
+                        address1: adr.address1.clone(),
+                        city: adr.city.clone(),
+                        state: adr.state.clone(),
+                        zip5: adr.zip5,
+                        zip4: adr.zip4,
+                        delivery_point: adr.delivery_point.clone(),
+                        org: string_to_opt(per.org.clone()),
+                        role: per.role.clone(),
+                        src: per.src.clone(),
+                        ..Default::default()
+                    };
+                    mailpieces.push(mp);
+                }
+            } else {
+                return Err(anyhow!("missing address for {}", per));
+            }
+        }
+
+        // Merge duplicate recipients, and households when configured.
+        let (mut mailpieces, report) = merge_mailpieces(mailpieces, CFG.household);
+        eprintln!("{report}");
+        report.save()?;
+
+        // Set mailpiece count.
+        mailing.mailpiece_cnt = mailpieces.len() as u16;
+
+        // Check labeling lists, rates and the mailer ID before serial IDs
+        // are allocated, so a failed run does not use up a range.
+        let lists = LabelingLists::load(DIR_LLISTS)?;
+        lists.validate(CFG.mailing_date())?;
+        let tables = RateTables::load(DIR_RATES)?;
+        let table = tables.select(CFG.mail_class, CFG.mailing_date())?;
+        // Tray barcodes have fewer serial digits than mailpiece barcodes.
+        let serial_cnt = 10u32.pow(imtb_serial_len(&CFG.mailer_id)? as u32);
+
+        // Sort by zip code, then name, for id generation.
+        mailpieces.sort_unstable_by_key(|o| (o.zip5, o.zip4, o.name_parts.key()));
+
+        // Allocate ids from the serial ledger.
+        // Each envelope gets a unique id.
+        // Id is used in the barcode.
+        let base_id = Ledger::allocate_serials(
+            &mailing.name,
+            mailpieces.len(),
+            CFG.last_mailpiece_id + 1,
+            CFG.mailing_date(),
+        )?;
+        for (idx, mp) in mailpieces.iter_mut().enumerate() {
+            mp.id = base_id + idx as u32;
+        }
+
+        // Pre-sort for USPS discount.
+        mailing.trays = presort_mailpieces(mailpieces, &lists, CFG.from.zip5);
+        eprintln!("{} trays", mailing.trays.len());
+
+        // Allocate tray serials from the serial ledger.
+        let base_serial = Ledger::allocate_tray_serials(
+            &mailing.name,
+            mailing.trays.len(),
+            serial_cnt,
+            CFG.mailing_date(),
+        )?;
+        for (idx, tray) in mailing.trays.iter_mut().enumerate() {
+            tray.serial = base_serial + idx as u32;
+        }
+
+        // Determine tray counts.
+        mailing.tray_1ft_cnt = mailing
             .trays
             .iter()
-            .map(|o| o.mailpieces.len())
-            .sum::<usize>() as f64;
+            .filter(|o| o.size == TraySize::OneFoot)
+            .count() as u8;
+        mailing.tray_2ft_cnt = mailing
+            .trays
+            .iter()
+            .filter(|o| o.size == TraySize::TwoFoot)
+            .count() as u8;
+
+        // Calculate prices.
+        mailing.set_postage(table)?;
+
+        // Write file to disk.
+        write_to_file(&mailing, FLE_PTH)?;
+
+        Ok(mailing)
+    }
+
+    /// Breaks down postage by rate category and sets the statement subtotals.
+    ///
+    /// 3-digit and AADC pieces share the AADC line of the statement.
+    pub fn set_postage(&mut self, table: &RateTable) -> Result<()> {
+        self.rate_table = table.to_string();
+        self.postage_lines = table.postage_lines(&self.trays)?;
+
+        let line = |categories: &[BarcodeId]| {
+            self.postage_lines
+                .iter()
+                .filter(|o| categories.contains(&o.category))
+                .fold((0, 0.0), |(cnt, subtotal), o| {
+                    (cnt + o.cnt, subtotal + o.subtotal)
+                })
+        };
+        let (five_dig_cnt, five_dig) = line(&[BarcodeId::FiveDigit]);
+        let (aadc_cnt, aadc) = line(&[BarcodeId::ThreeDigit, BarcodeId::Aadc]);
+        let (mixed_aadc_cnt, mixed_aadc) = line(&[BarcodeId::MixedAadc]);
+        self.five_dig_cnt = five_dig_cnt;
+        self.aadc_cnt = aadc_cnt;
+        self.mixed_aadc_cnt = mixed_aadc_cnt;
+        self.postage_subtotal_five_dig = five_dig;
+        self.postage_subtotal_aadc = aadc;
+        self.postage_subtotal_mixed_aadc = mixed_aadc;
+        self.part_a_subtotal = self.postage_lines.iter().map(|o| o.subtotal).sum();
+
+        Ok(())
+    }
+
+    /// The piece price of a rate category, or 0 when the mailing has none.
+    pub fn price(&self, category: BarcodeId) -> f64 {
+        self.postage_lines
+            .iter()
+            .find(|o| o.category == category)
+            .map_or(0.0, |o| o.price)
+    }
+
+    /// The number of mailpieces in all trays.
+    pub fn mps_len(&self) -> usize {
+        self.trays.iter().map(|o| o.mailpieces.len()).sum::<usize>()
+    }
+
+    /// The directory for mailing output files.
+    pub fn dir(&self) -> PathBuf {
+        let mut pth = PathBuf::from("mailings");
+        pth.push(&self.name);
+        pth
+    }
+
+    /// Encodes and verifies a barcode for each mailpiece.
+    ///
+    /// Writes the mailing to disk when a barcode is added.
+    pub fn add_barcodes(&mut self) -> Result<()> {
+        let mps_len = self.mps_len() as f64;
 
-        // Add barcodes to mailpieces.
         // Mail tray barcode_id is used in the barcode.
         let mut may_write = false;
         let mut cur_cnt: usize = 0;
-        for mail_tray in mailing.trays.iter_mut() {
-            if mail_tray.add_barcodes(cur_cnt, mps_len).await? {
+        for mail_tray in self.trays.iter_mut() {
+            if mail_tray.add_barcodes(cur_cnt, mps_len)? {
                 may_write = true;
             }
             cur_cnt += mail_tray.mailpieces.len();
@@ -194,62 +313,234 @@ impl Mailing {
         if may_write {
             // Save intermediate.
             // Write file to disk.
-            write_to_file(&mailing, FLE_PTH)?;
+            write_to_file(&self, FLE_PTH)?;
         }
 
-        // Create envelopes and letters.
+        Ok(())
+    }
+
+    /// Creates envelope and letter PDFs in the mailing directory.
+    ///
+    /// Replaces any previous mailing directory. Earlier outputs are kept
+    /// by `archive_mailing`.
+    pub fn create_envelopes_letters(&self) -> Result<()> {
+        CFG.indicia.validate(CFG.mail_class)?;
+
+        let pth = self.dir();
+        if is_dry_run() {
+            eprintln!("Dry run: skipping directory: {:?}", pth);
+        } else {
+            // Create the directory and any necessary parent directories
+            if pth.exists() {
+                // Delete any previous directory.
+                fs::remove_dir_all(&pth)?;
+            }
+            fs::create_dir_all(&pth)?;
+        }
+
+        let mps_len = self.mps_len() as f64;
         let mut cur_cnt: usize = 0;
-        for mail_tray in mailing.trays.iter() {
-            mail_tray.create_envelopes_letters(cur_cnt, mps_len, &pth.clone())?;
+        for mail_tray in self.trays.iter() {
+            mail_tray.create_envelopes_letters(cur_cnt, mps_len, &pth)?;
             cur_cnt += mail_tray.mailpieces.len();
         }
 
-        // // Fill in postage statement pdf.
-        // let mut ps = PostageStatement::load_new().await?;
-        // ps.fill_and_save(&mailing, pth.clone())?;
+        Ok(())
+    }
 
-        // eprintln!("{} mailpieces", mailing.mailpieces.len());
+    /// Creates a tray label PDF in the mailing directory.
+    ///
+    /// One label per tray, in tray order.
+    pub fn create_tray_labels(&self) -> Result<()> {
+        let mut lbl_doc = TrayLabelDocument::new(format!("{}_tray_labels", self.name));
+        for mail_tray in self.trays.iter() {
+            lbl_doc.create_label(mail_tray)?;
+        }
 
-        Ok(mailing)
+        if is_dry_run() {
+            eprintln!("Dry run: skipping {}", lbl_doc.name);
+            return Ok(());
+        }
+
+        // Save tray label document to disk.
+        let mut pth = self.dir();
+        fs::create_dir_all(&pth)?;
+        pth.push(&lbl_doc.name);
+        pth.set_extension("pdf");
+        eprintln!("creating {}", lbl_doc.name);
+        lbl_doc.save(&pth)
+    }
+
+    /// Fills in the postage statement PDF in the mailing directory.
+    pub async fn fill_postage_statement(&self) -> Result<()> {
+        let mut ps = PostageStatement::load_new().await?;
+        ps.fill_and_save(self, self.dir())
     }
 }
 
+impl fmt::Display for Mailing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "mailing {}", self.name)?;
+        writeln!(f, "  address validation date {}", self.adr_validation_date)?;
+        writeln!(f, "  {} mailpieces", numfmt(self.mailpiece_cnt as usize))?;
+        for tray in self.trays.iter() {
+            writeln!(
+                f,
+                "  tray {} {:?} {:?} {} {} mailpieces",
+                tray.name,
+                tray.size,
+                tray.barcode_id,
+                tray.dest.name,
+                tray.mailpieces.len()
+            )?;
+        }
+        writeln!(
+            f,
+            "  {} 1ft trays, {} 2ft trays",
+            self.tray_1ft_cnt, self.tray_2ft_cnt
+        )?;
+        writeln!(f, "  {}", self.rate_table)?;
+        for lne in self.postage_lines.iter() {
+            writeln!(f, "  {}", lne)?;
+        }
+        write!(f, "  part A subtotal ${:.2}", self.part_a_subtotal)
+    }
+}
+
+/// The minimum pieces for a 5-digit, 3-digit or AADC tray.
+///
+/// Smaller groups move to the next sort level.
+/// USPS Marketing Mail automation letters.
+pub const PRESORT_MIN: usize = 150;
+
 /// Pre-sort mail.
 ///
+/// Sorts into 5-digit, 3-digit, AADC and mixed AADC trays, in that order.
+/// Each level takes groups of at least `PRESORT_MIN` pieces; remaining
+/// pieces move to the next level. 3-digit trays combine prefixes with
+/// the L002 list, AADC trays use the L801 list, and the mixed AADC tray
+/// is labeled for the origin ZIP Code.
+///
 /// Determine barcode_id based on sort level.
-pub fn presort_mailpieces(mut mailpieces: Vec<Mailpiece>) -> Vec<MailTray> {
+pub fn presort_mailpieces(
+    mut mailpieces: Vec<Mailpiece>,
+    lists: &LabelingLists,
+    origin_zip5: u32,
+) -> Vec<MailTray> {
     let mut ret = Vec::new();
 
     // Sort for chunking.
     mailpieces.sort_unstable_by_key(|o| o.zip5);
 
-    let mut mixed_aadcs = Vec::with_capacity(mailpieces.len());
-    for (key, chunk) in &mailpieces.into_iter().chunk_by(|mp| mp.zip5) {
-        pub const PRESORT_MIN: usize = 200;
-        let grp: Vec<Mailpiece> = chunk.collect();
-        if grp.len() >= PRESORT_MIN {
-            eprintln!("{key:05} {}", grp.len());
-            ret.extend(segment_trays(BarcodeId::FiveDigit, grp));
-        } else {
-            mixed_aadcs.extend(grp);
-        }
+    // 5-digit trays.
+    let mps = presort_lvl(&mut ret, mailpieces, BarcodeId::FiveDigit, |mp| {
+        Some(Label {
+            zip: format!("{:05}", mp.zip5),
+            name: format!("{} {} {:05}", mp.city, mp.state, mp.zip5),
+        })
+    });
+
+    // 3-digit trays.
+    // A prefix missing from L002 is its own destination.
+    let mps = presort_lvl(&mut ret, mps, BarcodeId::ThreeDigit, |mp| {
+        lists
+            .label(SortLvl::ThreeDigitColA, mp.zip5)
+            .cloned()
+            .or_else(|| {
+                Some(Label {
+                    zip: format!("{:03}", mp.zip5 / 100),
+                    name: format!("{} {} {:03}", mp.city, mp.state, mp.zip5 / 100),
+                })
+            })
+    });
+
+    // AADC trays.
+    let mps = presort_lvl(&mut ret, mps, BarcodeId::Aadc, |mp| {
+        lists
+            .label(SortLvl::AADCLetterSizeMailingsA, mp.zip5)
+            .cloned()
+    });
+
+    // Mixed AADC tray.
+    eprintln!("mixed aadc {}", mps.len());
+    let dest = lists
+        .label(SortLvl::MixedAADC, origin_zip5)
+        .cloned()
+        .unwrap_or(Label {
+            zip: format!("{:05}", origin_zip5),
+            name: format!("MXD {:05}", origin_zip5),
+        });
+    if !mps.is_empty() {
+        ret.extend(segment_trays(BarcodeId::MixedAadc, dest, mps));
     }
 
-    eprintln!("mixed aadc {}", mixed_aadcs.len());
-    ret.extend(segment_trays(BarcodeId::MixedAadc, mixed_aadcs));
-
     // Set tray names.
-    let mut chr = 'A' as u32;
     for (idx, tray) in ret.iter_mut().enumerate() {
-        tray.name = char::from_u32(chr).expect("Invalid character").into();
-        chr += 1;
+        tray.name = tray_name(idx);
+    }
+
+    ret
+}
+
+/// The tray name for a tray index: `A` to `Z`, then `AA`, `AB` and so on.
+fn tray_name(idx: usize) -> String {
+    let mut ret = Vec::new();
+    let mut n = idx + 1;
+    while n > 0 {
+        n -= 1;
+        ret.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    ret.reverse();
+    String::from_utf8(ret).unwrap()
+}
+
+/// Pre-sort mailpieces into trays for one sort level.
+///
+/// Groups mailpieces by destination and creates trays for groups of at least
+/// `PRESORT_MIN` pieces. Returns mailpieces without a tray.
+fn presort_lvl<F>(
+    trays: &mut Vec<MailTray>,
+    mailpieces: Vec<Mailpiece>,
+    barcode_id: BarcodeId,
+    dest: F,
+) -> Vec<Mailpiece>
+where
+    F: Fn(&Mailpiece) -> Option<Label>,
+{
+    let mut ret = Vec::with_capacity(mailpieces.len());
+
+    // Group by destination ZIP.
+    let mut grps: BTreeMap<String, (Label, Vec<Mailpiece>)> = BTreeMap::new();
+    for mp in mailpieces {
+        match dest(&mp) {
+            Some(label) => grps
+                .entry(label.zip.clone())
+                .or_insert_with(|| (label, Vec::new()))
+                .1
+                .push(mp),
+            None => ret.push(mp),
+        }
+    }
+
+    for (_, (label, grp)) in grps {
+        if grp.len() >= PRESORT_MIN {
+            eprintln!("{:?} {} {}", barcode_id, label.name, grp.len());
+            trays.extend(segment_trays(barcode_id, label, grp));
+        } else {
+            ret.extend(grp);
+        }
     }
 
     ret
 }
 
 /// Segement pre-sorted groups into USPS trays.
-pub fn segment_trays(barcode_id: BarcodeId, mailpieces: Vec<Mailpiece>) -> Vec<MailTray> {
+pub fn segment_trays(
+    barcode_id: BarcodeId,
+    dest: Label,
+    mailpieces: Vec<Mailpiece>,
+) -> Vec<MailTray> {
     // 600 envelopes per 1ft tray.
     // Tray Length: 12 inches
     // Envelope Thickness: Varies, but a standard #10 envelope with a single sheet of paper is approximately 0.02 inches thick.
@@ -267,6 +558,8 @@ pub fn segment_trays(barcode_id: BarcodeId, mailpieces: Vec<Mailpiece>) -> Vec<M
             name: "".into(),
             size: OneFoot,
             barcode_id,
+            dest: dest.clone(),
+            serial: 0,
             mailpieces,
         });
     } else if mailpieces.len() <= CAP_2FOOT {
@@ -274,6 +567,8 @@ pub fn segment_trays(barcode_id: BarcodeId, mailpieces: Vec<Mailpiece>) -> Vec<M
             name: "".into(),
             size: TwoFoot,
             barcode_id,
+            dest: dest.clone(),
+            serial: 0,
             mailpieces,
         });
     } else {
@@ -285,6 +580,8 @@ pub fn segment_trays(barcode_id: BarcodeId, mailpieces: Vec<Mailpiece>) -> Vec<M
                 name: "".into(),
                 size: TraySize::TwoFoot,
                 barcode_id,
+                dest: dest.clone(),
+                serial: 0,
                 mailpieces: left.to_vec(),
             });
             remaining_pieces = right;
@@ -296,6 +593,8 @@ pub fn segment_trays(barcode_id: BarcodeId, mailpieces: Vec<Mailpiece>) -> Vec<M
                 name: "".into(),
                 size: TraySize::TwoFoot,
                 barcode_id,
+                dest: dest.clone(),
+                serial: 0,
                 mailpieces: remaining_pieces.to_vec(),
             });
         } else if !remaining_pieces.is_empty() {
@@ -303,6 +602,8 @@ pub fn segment_trays(barcode_id: BarcodeId, mailpieces: Vec<Mailpiece>) -> Vec<M
                 name: "".into(),
                 size: TraySize::OneFoot,
                 barcode_id,
+                dest: dest.clone(),
+                serial: 0,
                 mailpieces: remaining_pieces.to_vec(),
             });
         }
@@ -317,18 +618,24 @@ pub struct MailTray {
     pub name: String,
     pub size: TraySize,
     pub barcode_id: BarcodeId,
+    /// The tray destination.
+    #[serde(default)]
+    pub dest: Label,
+    /// The tray barcode serial, allocated from the serial ledger.
+    #[serde(default)]
+    pub serial: u32,
     pub mailpieces: Vec<Mailpiece>,
 }
 
 impl MailTray {
     // See "Intelligent Mail Barcode Technical Resource Guide" PDF.
     // See https://postalpro.usps.com/node/221.
-    pub async fn add_barcodes(&mut self, cur_cnt: usize, mps_len: f64) -> Result<bool> {
+    pub fn add_barcodes(&mut self, cur_cnt: usize, mps_len: f64) -> Result<bool> {
         let mut self_clone = self.clone();
         let mp_len = self.mailpieces.len() as f64;
 
-        // Fetch barcode encoding for each mailpiece.
-        let mut did_fetch = false;
+        // Encode barcode for each mailpiece.
+        let mut did_encode = false;
         for (idx, mp) in self_clone
             .mailpieces
             .iter()
@@ -336,41 +643,48 @@ impl MailTray {
             .filter(|(_, mp)| mp.barcode.is_empty())
         // .take(1)
         {
-            did_fetch = true;
+            did_encode = true;
             let pct = ((((cur_cnt + idx) as f64 + 1.0) / mps_len) * 100.0) as u8;
             eprintln!("  {}% {}", pct, mp);
 
-            // Create routing code (zip + delivery point).
-            // The Routing Code field is an optional field, which may contain a
-            // 5-digit ZIP Code, a 9-digit ZIP+4 code, or an 11-digit delivery
-            // point code. When used on letters for automation-rate eligibility purposes,
-            // the routing code must contain a delivery point code from CASS-certified
-            // software that accurately matches the delivery address.
-            // From "Intelligent Mail Barcode Technical Resource Guide" PDF.
-            // See https://postalpro.usps.com/node/221.
-            let mut routing_code = if mp.zip4 != 0 {
-                format!("{:05}{:04}", mp.zip5, mp.zip4)
-            } else {
-                format!("{:05}", mp.zip5)
-            };
-            if mp.zip4 != 0 {
-                if let Some(delivery_point) = &mp.delivery_point {
-                    routing_code.push_str(delivery_point);
-                }
-            }
-
-            // eprintln!("  routing_code:{routing_code}");
             self.mailpieces[idx].barcode = encode_barcode(
                 &format!("{}", self.barcode_id),
-                STID_RSR,
+                CFG.stid()?,
                 &CFG.mailer_id,
                 &format!("{:06}", mp.id),
-                &routing_code,
+                &mp.routing_code(),
+            )?;
+        }
+
+        // Verify each barcode against its mailpiece.
+        // A barcode read from disk may be stale.
+        for mp in self.mailpieces.iter() {
+            verify_barcode(
+                &mp.barcode,
+                &format!("{}", self.barcode_id),
+                CFG.stid()?,
+                &CFG.mailer_id,
+                &format!("{:06}", mp.id),
+                &mp.routing_code(),
             )
-            .await?;
+            .map_err(|err| anyhow!("invalid barcode for {}: {}", mp, err))?;
         }
 
-        Ok(did_fetch)
+        Ok(did_encode)
+    }
+
+    /// The 24-digit Intelligent Mail tray barcode.
+    ///
+    /// The serial ledger keeps the tray serial unique for 45 days.
+    pub fn imtb(&self) -> Result<String> {
+        imtb_digits(
+            &self.dest.zip,
+            cin(CFG.mail_class, self.barcode_id)?,
+            PROC_CODE_LTR,
+            &CFG.mailer_id,
+            self.serial,
+        )
+        .map_err(|err| anyhow!("invalid tray barcode for tray {}: {}", self.name, err))
     }
 
     pub fn create_envelopes_letters<P>(&self, cur_cnt: usize, mps_len: f64, pth: P) -> Result<()>
@@ -378,7 +692,28 @@ impl MailTray {
         P: AsRef<Path>,
     {
         // Read letter template from disk.
+        // Parse merge fields in each paragraph once.
         let ltr_tmpl = letter_template()?;
+        let tmpls = ltr_tmpl
+            .elms
+            .iter()
+            .filter_map(|elm| match elm {
+                Elm::Par(par) => Some(Tmpl::parse(&par.txt)),
+                Elm::PagBrk => None,
+            })
+            .collect::<Result<Vec<_>>>()
+            .map_err(|err| anyhow!("invalid letter template {}: {}", FLE_PTH_LTR, err))?;
+
+        // Window envelopes show addresses printed on the letter.
+        // Keep letter text below the windows.
+        let is_window = CFG.envelope.is_window();
+        if is_window && ltr_tmpl.mrg.top.0 < min_top_margin(&CFG.windows) {
+            return Err(anyhow!(
+                "letter template top margin {:.2} in is above the envelope windows; use {:.2} in",
+                ltr_tmpl.mrg.top.0,
+                min_top_margin(&CFG.windows)
+            ));
+        }
 
         // 50 chunk size is based on capacity of an envelope printer and paper folding machine.
         const CHUNK_SIZE: usize = 50;
@@ -419,42 +754,79 @@ impl MailTray {
             let mut ltr = ltr_tmpl.clone_clear();
 
             // Create a pdf document for multiple envelopes.
-            let mut env_doc = EnvelopeDocument::new(env_name);
+            // Window envelopes skip envelope printing.
+            let mut env_doc = (!is_window).then(|| EnvelopeDocument::new(env_name, CFG.envelope));
+            let mut win_blks = Vec::new();
+            let mut pg_cnt = 0;
 
             // Iterate through each mailpiece in the current chunk.
             for (mp_idx, mp) in chunk {
                 let pct = ((((cur_cnt + mp_idx) as f64 + 1.0) / mps_len) * 100.0) as u8;
                 eprintln!("  {}% {}", pct, mp);
 
-                // Create envelope.
-                env_doc.create_page(mp, mp_idx % CHUNK_SIZE == 0);
-
                 // Create letter.
-                // Clone letter template with text.
-                let mut cur_ltr = ltr_tmpl.clone();
-                // Replace placeholder text with actual name.
-                cur_ltr.replace_par_at(0, "{{name}}", &mp.name);
+                // Merge fields into each template paragraph, skipping
+                // paragraphs left blank by conditional blocks.
+                let merged = Letter::merge(&tmpls, &MergeFields::new(mp), &CFG.from.name)
+                    .map_err(|err| anyhow!("unable to merge letter for {}: {}", mp, err))?;
+                let mut cur_ltr = ltr_tmpl.clone_clear();
+                let mut txts = merged.paragraphs.into_iter();
+                for elm in ltr_tmpl.elms.iter() {
+                    match elm {
+                        Elm::Par(par) => {
+                            let txt = txts.next().unwrap_or_default();
+                            if !txt.is_empty() {
+                                cur_ltr.add_par(par.clone().set_txt(txt));
+                            }
+                        }
+                        Elm::PagBrk => cur_ltr.add_pag_brk(),
+                    }
+                }
+                let pg1 = pg_cnt + 1;
+                pg_cnt += cur_ltr.seg_pags().len();
+
+                // Create envelope, or place the letter address blocks.
+                match env_doc.as_mut() {
+                    Some(env_doc) => env_doc.create_page(mp, mp_idx % CHUNK_SIZE == 0)?,
+                    None => {
+                        let blk = WindowBlock::new(&CFG.windows, from_lines(), mp)?;
+                        win_blks.push((pg1, blk));
+                    }
+                }
+
                 // Copy paragraphs to destination letter.
-                ltr.copy_pars(cur_ltr.clone());
+                ltr.copy_pars(cur_ltr);
                 // Add a page break.
                 ltr.add_pag_brk();
             }
 
+            if is_dry_run() {
+                eprintln!("Dry run: skipping {}", ltr_name);
+                continue;
+            }
+
             // Create path.
             let mut pth = pth.as_ref().to_path_buf();
 
             // Save envelope document to disk.
-            pth.push(env_doc.name);
-            pth.set_extension("pdf");
-            env_doc
-                .doc
-                .save(&mut BufWriter::new(File::create(&pth).unwrap()))?;
+            if let Some(env_doc) = env_doc {
+                pth.push(env_doc.name);
+                pth.set_extension("pdf");
+                env_doc
+                    .doc
+                    .save(&mut BufWriter::new(File::create(&pth).unwrap()))?;
+                pth.pop();
+            }
 
             // Save letter document to disk.
-            pth.pop();
             pth.push(ltr_name);
             pth.set_extension("");
             ltr.save_pdf(&pth)?;
+
+            // Write address blocks on each letter for window envelopes.
+            if is_window {
+                add_window_blocks(pth.with_extension("pdf"), &win_blks)?;
+            }
         }
 
         Ok(())
@@ -465,8 +837,27 @@ pub fn letter_template() -> Result<Doc> {
     read_from_file::<Doc>(FLE_PTH_LTR)
 }
 
+/// Sets the mailing configuration path.
+///
+/// Call before the first use of `CFG`.
+pub fn set_cfg_pth(pth: &str) -> Result<()> {
+    CFG_PTH
+        .set(pth.into())
+        .map_err(|_| anyhow!("mailing configuration path already set"))
+}
+
+/// The mailing configuration path.
+///
+/// Defaults to `mailing_cfg.json`.
+pub fn cfg_pth() -> &'static str {
+    CFG_PTH.get().map_or(FLE_PTH_CFG, |pth| pth.as_str())
+}
+
 pub fn mailing_cfg() -> Result<MailingCfg> {
-    read_from_file::<MailingCfg>(FLE_PTH_CFG)
+    let cfg = read_from_file::<MailingCfg>(cfg_pth())?;
+    cfg.validate()
+        .map_err(|err| anyhow!("invalid mailing configuration {}: {}", cfg_pth(), err))?;
+    Ok(cfg)
 }
 
 /// STID 301 is USPS Marketing Mail, Basic automation, No Address Corrections.
@@ -477,7 +868,8 @@ pub fn mailing_cfg() -> Result<MailingCfg> {
 /// https://postalpro.usps.com/mailing/service-type-identifiers.
 pub const STID_NO_ADR: &str = "301";
 
-/// STID 272 is USPS Marketing Mail, Basic automation, with Return Service Requested.
+/// STID 272 is USPS Marketing Mail, Basic automation, with manual address
+/// corrections from a printed endorsement, such as Return Service Requested.
 ///
 /// For use with USPS barcode.
 ///
@@ -485,6 +877,19 @@ pub const STID_NO_ADR: &str = "301";
 /// https://postalpro.usps.com/mailing/service-type-identifiers.
 pub const STID_RSR: &str = "272";
 
+/// STID 300 is First-Class Mail, Basic automation, No Address Corrections.
+///
+/// See the Service Type IDentifier (STID) Table
+/// https://postalpro.usps.com/mailing/service-type-identifiers.
+pub const STID_FCM_NO_ADR: &str = "300";
+
+/// STID 260 is First-Class Mail, Basic automation, with manual address
+/// corrections from a printed endorsement, such as Return Service Requested.
+///
+/// See the Service Type IDentifier (STID) Table
+/// https://postalpro.usps.com/mailing/service-type-identifiers.
+pub const STID_FCM_RSR: &str = "260";
+
 // USPS serial_id:
 // The USPS Intelligent Mail Barcode (IMb) contains several components, one of which is the serial number. The serial number within the IMb can be used in different ways depending on the mailer's needs and USPS requirements. Here's how it works:
 //
@@ -499,12 +904,119 @@ pub struct MailingCfg {
     pub crid: String,
     pub eps_id: String,
     pub nonprofit_auth_id: String,
+    /// The mail class, which sets STIDs, prices, the postage statement
+    /// and envelope markings.
+    ///
+    /// Defaults to USPS Marketing Mail Nonprofit.
+    #[serde(default)]
+    pub mail_class: MailClass,
+    /// The last serial ID used before the serial ledger.
+    ///
+    /// Seeds an empty ledger.
     pub last_mailpiece_id: u32,
+    /// The envelope size.
+    ///
+    /// Defaults to a #10 envelope.
+    #[serde(default)]
+    pub envelope: EnvelopeSpec,
+    /// Window geometry for window envelopes.
+    ///
+    /// Defaults to a common #10 double-window envelope.
+    #[serde(default)]
+    pub windows: Windows,
     pub indicia: Indicia,
+    /// The ancillary service endorsement, which sets the STID.
+    ///
+    /// Defaults to Return Service Requested.
+    #[serde(default)]
+    pub endorsement: Endorsement,
+    /// Where the endorsement is printed.
+    ///
+    /// Defaults to below the postage area.
+    #[serde(default)]
+    pub endorsement_pos: EndorsementPos,
+    /// Whether recipients at one delivery point share one mailpiece,
+    /// up to `HOUSEHOLD_MAX` recipients.
+    ///
+    /// Defaults to one mailpiece per recipient.
+    #[serde(default)]
+    pub household: bool,
+    /// Address selection policies by source or role.
+    ///
+    /// The first matching policy applies. Defaults to every address.
+    #[serde(default)]
+    pub adr_policies: Vec<AdrPolicy>,
+    /// Addresses chosen by hand for a person, ahead of any policy.
+    #[serde(default)]
+    pub adr_overrides: Vec<AdrOverride>,
     pub from: Mailpiece,
     pub ps: PostageStatementCfg,
 }
 
+impl MailingCfg {
+    /// The mailing date from the postage statement configuration.
+    ///
+    /// Defaults to today when the date is missing. `validate` rejects an
+    /// invalid date.
+    pub fn mailing_date(&self) -> NaiveDate {
+        parse_date(&self.ps.mailing_date).unwrap_or_else(|| Local::now().date_naive())
+    }
+
+    /// The STID for mailpiece barcodes.
+    ///
+    /// Matches the printed endorsement.
+    pub fn stid(&self) -> Result<&'static str> {
+        self.endorsement.stid(self.mail_class)
+    }
+
+    /// Checks for settings the mailing cannot print or price.
+    pub fn validate(&self) -> Result<()> {
+        self.stid()?;
+
+        // Rates, labeling lists and the statement use the mailing date.
+        if self.ps.mailing_date.is_empty() {
+            eprintln!("ps.mailing_date not set; using today");
+        } else if parse_date(&self.ps.mailing_date).is_none() {
+            return Err(anyhow!(
+                "invalid ps.mailing_date {:?}",
+                self.ps.mailing_date
+            ));
+        }
+
+        // The mail class needs prices and a postage statement field map.
+        RateTables::load(DIR_RATES)?.select(self.mail_class, self.mailing_date())?;
+        let form = self.mail_class.statement();
+        let map = FieldMap::load(&self.ps.field_map, form)?;
+        if map.form != form {
+            return Err(anyhow!(
+                "{} needs a {} field map, not {}; set ps.field_map",
+                self.mail_class,
+                form,
+                map.form
+            ));
+        }
+
+        // Window envelopes print only the letter, so the indicia, markings
+        // and any endorsement must be preprinted on the envelope.
+        // A preprinted endorsement would not match the STID.
+        if self.envelope.is_window() {
+            if self.endorsement != Endorsement::None {
+                return Err(anyhow!(
+                    "window envelopes do not print the {:?} endorsement; use none",
+                    self.endorsement
+                ));
+            }
+            if self.indicia.fmt != IndiciaFmt::None {
+                return Err(anyhow!(
+                    "window envelopes do not print the {:?} indicia; use none",
+                    self.indicia.fmt
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct PostageStatementCfg {
     pub adr: Mailpiece,
@@ -513,12 +1025,17 @@ pub struct PostageStatementCfg {
     pub post_office_mailing: String,
     pub mailing_date: String,
     pub last_statement_id: u16,
+    /// The postage statement field map path.
+    ///
+    /// Empty uses the built-in map for the mail class's statement.
+    #[serde(default)]
+    pub field_map: String,
 }
 
 /// USPS barcode identifier.
 /// From "Intelligent Mail Barcode Technical Resource Guide" PDF.
 /// See https://postalpro.usps.com/node/221.
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum BarcodeId {
     Default,      // 00 - Default / No OEL Information
     CarrierRoute, // 10 - Carrier Route (CR), Enhanced Carrier Route (ECR), and FIRM
@@ -546,8 +1063,11 @@ pub enum TraySize {
     TwoFoot,
 }
 
-#[derive(Debug)]
-enum SortLvl {
+/// A presort level and its USPS labeling list.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter,
+)]
+pub enum SortLvl {
     FiveDigit,                     // 5DIG - 5 Digit
     ThreeDigitColA,                // L002A - 3 Digit Col A
     ThreeDigitColB1,               // L002B1 - 3 Digit Col B
@@ -574,3 +1094,258 @@ enum SortLvl {
     AADCLetterSizeMailingsB, // L801B - AADCs - Letter-Size Mailings
     None,                 // No value selected
 }
+
+impl SortLvl {
+    /// The labeling list code, such as `L002A`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SortLvl::FiveDigit => "5DIG",
+            SortLvl::ThreeDigitColA => "L002A",
+            SortLvl::ThreeDigitColB1 => "L002B1",
+            SortLvl::ThreeDigitColB2 => "L002B2",
+            SortLvl::ThreeDigitColC => "L002C",
+            SortLvl::ThreeDigitSchemeSortation => "L003",
+            SortLvl::ADC3DigitSortationA => "L004A",
+            SortLvl::ADC3DigitSortationB => "L004B",
+            SortLvl::ADC3DigitSortationC => "L004C",
+            SortLvl::SCFSortation => "L005",
+            SortLvl::FiveDigitScheme => "L007",
+            SortLvl::MixedADCsA => "L009A",
+            SortLvl::MixedADCsB => "L009B",
+            SortLvl::NDCASFEntry => "L010",
+            SortLvl::NonNDCASFEntryA => "L011A",
+            SortLvl::NonNDCASFEntryB => "L011B",
+            SortLvl::FiveDigitZIPSchemeCombination => "L012",
+            SortLvl::ADCOptionalSortation => "L015",
+            SortLvl::Omx => "L201A",
+            SortLvl::MixedAADC => "L201B",
+            SortLvl::NDCs => "L601",
+            SortLvl::ASFs => "L602",
+            SortLvl::AADCLetterSizeMailingsA => "L801A",
+            SortLvl::AADCLetterSizeMailingsB => "L801B",
+            SortLvl::None => "",
+        }
+    }
+
+    /// Sort levels with a labeling list.
+    pub fn lists() -> impl Iterator<Item = SortLvl> {
+        SortLvl::iter().filter(|o| o.code().starts_with('L'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    fn mps(zip5: u32, cnt: usize) -> Vec<Mailpiece> {
+        (0..cnt)
+            .map(|_| Mailpiece {
+                city: "WASHINGTON".into(),
+                state: "DC".into(),
+                zip5,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_presort_mailpieces() {
+        let mut lists = LabelingLists::default();
+        let mut labels = BTreeMap::new();
+        labels.insert(
+            "100".into(),
+            Label {
+                zip: "100".into(),
+                name: "AADC NEW YORK NY 100".into(),
+            },
+        );
+        labels.insert(
+            "101".into(),
+            Label {
+                zip: "100".into(),
+                name: "AADC NEW YORK NY 100".into(),
+            },
+        );
+        lists.insert(LabelingList {
+            sort_lvl: SortLvl::AADCLetterSizeMailingsA,
+            effective: None,
+            labels,
+        });
+
+        let mut mailpieces = Vec::new();
+        // 5-digit tray.
+        mailpieces.extend(mps(20510, PRESORT_MIN));
+        // 3-digit tray from several ZIP Codes.
+        mailpieces.extend(mps(20515, 100));
+        mailpieces.extend(mps(20520, 50));
+        // AADC tray from several prefixes.
+        mailpieces.extend(mps(10001, 100));
+        mailpieces.extend(mps(10101, 50));
+        // Mixed AADC.
+        mailpieces.extend(mps(90210, 10));
+        mailpieces.extend(mps(10201, 10));
+
+        let trays = presort_mailpieces(mailpieces, &lists, 94105);
+        let lvls: Vec<(BarcodeId, &str, usize)> = trays
+            .iter()
+            .map(|o| (o.barcode_id, o.dest.zip.as_str(), o.mailpieces.len()))
+            .collect();
+        assert_eq!(
+            lvls,
+            vec![
+                (BarcodeId::FiveDigit, "20510", 150),
+                (BarcodeId::ThreeDigit, "205", 150),
+                (BarcodeId::Aadc, "100", 150),
+                (BarcodeId::MixedAadc, "94105", 20),
+            ]
+        );
+        let names: Vec<&str> = trays.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, vec!["A", "B", "C", "D"]);
+
+        // No mixed AADC tray without mixed AADC pieces.
+        let trays = presort_mailpieces(mps(20510, PRESORT_MIN), &lists, 94105);
+        let lvls: Vec<BarcodeId> = trays.iter().map(|o| o.barcode_id).collect();
+        assert_eq!(lvls, vec![BarcodeId::FiveDigit]);
+    }
+
+    #[test]
+    fn test_tray_name() {
+        let cases = vec![
+            (0, "A"),
+            (25, "Z"),
+            (26, "AA"),
+            (27, "AB"),
+            (701, "ZZ"),
+            (702, "AAA"),
+        ];
+
+        for (idx, expected) in cases {
+            assert_eq!(tray_name(idx), expected, "{idx}");
+        }
+    }
+
+    #[test]
+    fn test_segment_trays() {
+        let cases = vec![
+            (600, vec![OneFoot]),
+            (601, vec![TwoFoot]),
+            (1_800, vec![TwoFoot, OneFoot]),
+            (2_401, vec![TwoFoot, TwoFoot, OneFoot]),
+            (3_001, vec![TwoFoot, TwoFoot, TwoFoot]),
+        ];
+
+        for (cnt, expected) in cases {
+            let trays = segment_trays(BarcodeId::MixedAadc, Label::default(), mps(20510, cnt));
+            let sizes: Vec<TraySize> = trays.iter().map(|o| o.size.clone()).collect();
+            assert_eq!(sizes, expected, "{cnt}");
+            assert_eq!(trays.iter().map(|o| o.mailpieces.len()).sum::<usize>(), cnt);
+        }
+    }
+
+    #[test]
+    fn test_tray_serials() {
+        let today = date(2024, 7, 1);
+        let mailer_id = "901234567";
+        let mut ledger = Ledger::default();
+
+        // Mailpiece serial IDs past the 4-digit tray serial field.
+        let mut mailpieces = mps(20510, 2_000);
+        let alloc = ledger
+            .allocate("2024-Q3", mailpieces.len(), 10_000, today)
+            .unwrap();
+        for (idx, mp) in mailpieces.iter_mut().enumerate() {
+            mp.id = alloc.first + idx as u32;
+        }
+        let mut trays = segment_trays(BarcodeId::FiveDigit, Label::default(), mailpieces);
+        assert!(trays.iter().all(|o| o.mailpieces[0].id >= 10_000));
+
+        let serial_cnt = 10u32.pow(imtb_serial_len(mailer_id).unwrap() as u32);
+        let alloc = ledger
+            .allocate_trays("2024-Q3", trays.len(), serial_cnt, today)
+            .unwrap();
+        for (idx, tray) in trays.iter_mut().enumerate() {
+            tray.serial = alloc.first + idx as u32;
+        }
+        let digits: Vec<String> = trays
+            .iter()
+            .map(|o| imtb_digits("20510", "554", PROC_CODE_LTR, mailer_id, o.serial).unwrap())
+            .collect();
+        assert_eq!(
+            digits,
+            vec!["205105540009012345670000", "205105540009012345670001"]
+        );
+    }
+
+    #[test]
+    fn test_cfg_validate() {
+        let window = EnvelopeSpec::No10Window;
+        let rsr = Endorsement::ReturnServiceRequested;
+        let cases = vec![
+            (EnvelopeSpec::No10, rsr, IndiciaFmt::Nonprofit, true),
+            (window, Endorsement::None, IndiciaFmt::None, true),
+            (window, rsr, IndiciaFmt::None, false),
+            (window, Endorsement::None, IndiciaFmt::Nonprofit, false),
+            (
+                EnvelopeSpec::No10,
+                Endorsement::ChangeServiceRequested,
+                IndiciaFmt::None,
+                false,
+            ),
+        ];
+
+        for (envelope, endorsement, fmt, expected) in cases {
+            let cfg = MailingCfg {
+                envelope,
+                endorsement,
+                indicia: Indicia {
+                    fmt,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            assert_eq!(
+                cfg.validate().is_ok(),
+                expected,
+                "{envelope:?} {endorsement:?} {fmt:?}"
+            );
+        }
+
+        // Each mail class has built-in rates and a built-in field map.
+        let cases = vec![
+            MailClass::Nonprofit,
+            MailClass::Marketing,
+            MailClass::FirstClass,
+        ];
+        for mail_class in cases {
+            let cfg = MailingCfg {
+                mail_class,
+                ..Default::default()
+            };
+            assert!(cfg.validate().is_ok(), "{mail_class}");
+        }
+
+        // A mailing date must parse when set.
+        let cases = vec![
+            ("", true),
+            ("2024-07-14", true),
+            ("July 14, 2024", true),
+            ("2024-13-01", false),
+            ("next week", false),
+        ];
+        for (mailing_date, expected) in cases {
+            let mut cfg = MailingCfg::default();
+            cfg.ps.mailing_date = mailing_date.into();
+            assert_eq!(cfg.validate().is_ok(), expected, "{mailing_date}");
+        }
+    }
+
+    #[test]
+    fn test_sort_lvl_lists() {
+        let codes: Vec<&str> = SortLvl::lists().map(|o| o.code()).collect();
+        assert!(codes.contains(&"L002A"));
+        assert!(codes.contains(&"L801A"));
+        assert!(!codes.contains(&"5DIG"));
+        assert!(!codes.contains(&""));
+    }
+}
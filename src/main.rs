@@ -4,57 +4,251 @@
 extern crate lazy_static;
 
 use anyhow::{anyhow, Result};
+mod adr_policy;
+mod archive;
+mod cache;
+mod cli;
 mod core;
+mod dedup;
+mod diff;
 mod envelope;
 mod executive;
+mod field_map;
+#[cfg(test)]
+mod fixtures;
+mod honorific;
 mod house;
+mod imb;
+mod imtb;
+mod layout;
+mod ledger;
+mod letter;
+mod llist;
 mod mailing;
 mod military;
 mod models;
 mod nasa;
 mod observer;
 mod prsr;
+mod rates;
 mod senate;
+mod source;
 mod state;
+mod tray_label;
 mod usps;
+mod window;
 mod postage_statement;
+use archive::*;
+use cache::*;
+use cli::*;
 use core::*;
+use diff::*;
 use executive::*;
+use field_map::*;
 use house::*;
+use imb::*;
+use imtb::*;
+use ledger::*;
+use llist::*;
 use mailing::*;
 use military::*;
 use models::*;
 use nasa::*;
 use observer::*;
 use prsr::*;
+use rates::*;
 use senate::*;
+use source::*;
 use state::*;
+use tray_label::*;
 use usps::*;
 
 #[tokio::main]
 pub async fn main() -> Result<()> {
-    // Load addresses from disk or network.
-    let mut military = Military::load().await?;
-    let mut nasa = Nasa::load().await?;
-    let mut executive = Executive::load().await?;
-    let mut senate = Senate::load().await?;
-    let mut house = House::load().await?;
-    let mut state = State::load().await?;
-    let mut observer = Observer::load().await?;
-
-    // Combine people into single list.
+    let cli = Cli::parse(std::env::args().skip(1))?;
+    if cli.cmd == Cmd::Help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+
+    // Resolve the config path before changing the working directory.
+    if let Some(cfg) = &cli.cfg {
+        let cfg = std::path::absolute(cfg)?;
+        set_cfg_pth(&cfg.to_string_lossy())?;
+    }
+    if let Some(dir) = &cli.dir {
+        std::env::set_current_dir(dir)?;
+    }
+    set_dry_run(cli.dry_run);
+
+    match cli.cmd {
+        Cmd::All => {
+            // Load addresses from disk or network.
+            let srcs = load_sources().await?;
+
+            // Report roster changes since the previous mailing.
+            let name = Mailing::new().name;
+            let snap = snapshot(&srcs);
+            if let Some(prev) = prev_snapshot_name(&name)? {
+                let diff = Diff::new(&prev, &read_snapshot(&prev)?, &name, &snap);
+                eprintln!("{diff}");
+                save_diff(&diff)?;
+            }
+            save_snapshot(&name, &snap)?;
+
+            let mut pers = persons(&srcs);
+
+            // Create mailing.
+            Mailing::load(&mut pers).await?;
+        }
+        Cmd::Fetch(src) => fetch(&src).await?,
+        Cmd::Parse => {
+            let pers = load_persons().await?;
+            for per in pers.iter().filter(|per| per.adr_len() == 0) {
+                eprintln!("missing address for {}", per);
+            }
+            let adr_cnt = pers.iter().map(|per| per.adr_len()).sum::<usize>();
+            eprintln!("{} addresses", adr_cnt);
+        }
+        Cmd::Standardize => standardize().await?,
+        Cmd::Presort => {
+            let pers = load_persons().await?;
+            let mailing = Mailing::presort(&pers)?;
+            println!("{mailing}");
+        }
+        Cmd::Barcode => read_mailing()?.add_barcodes()?,
+        Cmd::Render => {
+            let mailing = read_mailing()?;
+            if let Some(mp) = mailing
+                .trays
+                .iter()
+                .flat_map(|o| o.mailpieces.iter())
+                .find(|mp| mp.barcode.is_empty())
+            {
+                return Err(anyhow!("missing barcode for {}; run barcode first", mp));
+            }
+            mailing.create_envelopes_letters()?;
+            mailing.create_tray_labels()?;
+        }
+        Cmd::Labels => read_mailing()?.create_tray_labels()?,
+        Cmd::Statement => read_mailing()?.fill_postage_statement().await?,
+        Cmd::Report => println!("{}", read_mailing()?),
+        Cmd::Lists => {
+            let lists = LabelingLists::load(DIR_LLISTS)?;
+            let mailing_date = CFG.mailing_date();
+            for list in lists.0.values() {
+                println!(
+                    "{} {} prefixes, {}",
+                    list.sort_lvl.code(),
+                    list.labels.len(),
+                    list.check(mailing_date)
+                );
+            }
+        }
+        Cmd::Ledger => {
+            let ledger = Ledger::read()?;
+            println!("{ledger}");
+            let overlaps = ledger.overlaps(CFG.mailing_date());
+            for (a, b) in overlaps.iter() {
+                eprintln!(
+                    "overlap: {} {:06} and {} {:06}",
+                    a.mailing, a.first, b.mailing, b.first
+                );
+            }
+            if !overlaps.is_empty() {
+                return Err(anyhow!("{} serial ID overlaps", overlaps.len()));
+            }
+        }
+        Cmd::Archive => {
+            archive_mailing(&read_mailing()?)?;
+        }
+        Cmd::Diff { old, new } => {
+            let (new_name, new_snap) = match new {
+                Some(new) => {
+                    let new_snap = read_snapshot(&new)?;
+                    (new, new_snap)
+                }
+                None => (Mailing::new().name, snapshot(&load_sources().await?)),
+            };
+            let old_name = match old {
+                Some(old) => old,
+                None => prev_snapshot_name(&new_name)?
+                    .ok_or(anyhow!("no snapshot before {new_name}"))?,
+            };
+            let diff = Diff::new(&old_name, &read_snapshot(&old_name)?, &new_name, &new_snap);
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                println!("{diff}");
+            }
+        }
+        Cmd::Cache(CacheCmd::Ls) => cache_ls()?,
+        Cmd::Cache(CacheCmd::Purge { all }) => cache_purge(all)?,
+        Cmd::Cache(CacheCmd::Verify) => cache_verify()?,
+        Cmd::Help => {}
+    }
+
+    Ok(())
+}
+
+/// Load each roster from disk or network.
+async fn load_sources() -> Result<Vec<Box<dyn Source>>> {
+    let mut srcs = sources();
+    for src in srcs.iter_mut() {
+        src.load().await?;
+    }
+    Ok(srcs)
+}
+
+/// Load people from each roster on disk or network.
+async fn load_persons() -> Result<Vec<Person>> {
+    Ok(persons(&load_sources().await?))
+}
+
+/// Combine people of each roster into single list.
+fn persons(srcs: &[Box<dyn Source>]) -> Vec<Person> {
     let mut pers = Vec::with_capacity(1_076);
-    pers.extend(military.persons);
-    pers.extend(nasa.persons);
-    pers.extend(executive.persons);
-    pers.extend(senate.persons);
-    pers.extend(house.persons);
-    pers.extend(state.persons);
-    pers.extend(observer.persons);
+    for src in srcs {
+        pers.extend(src.persons().iter().map(|per| Person {
+            org: src.name().into(),
+            role: Some(src.role()),
+            src: src.key().into(),
+            ..per.clone()
+        }));
+    }
     eprintln!("{} people", pers.len());
+    pers
+}
+
+/// Fetch a roster from network, replacing any roster on disk.
+async fn fetch(key: &str) -> Result<()> {
+    let srcs = match key {
+        "all" => sources()
+            .into_iter()
+            .filter(|src| !src.is_manual())
+            .collect(),
+        _ => vec![source(key)?],
+    };
+    for mut src in srcs {
+        src.fetch().await?;
+        src.fetch_adrs().await?;
+    }
 
-    // Create mailing.
-    let mut mailing = Mailing::load(&mut pers).await?;
+    Ok(())
+}
+
+/// Standardize the addresses of each roster with the USPS.
+async fn standardize() -> Result<()> {
+    for mut src in sources() {
+        src.load().await?;
+        standardize_persons(src.persons_mut()).await?;
+        src.save()?;
+    }
 
     Ok(())
 }
+
+/// Read the mailing from disk.
+fn read_mailing() -> Result<Mailing> {
+    Mailing::read().map_err(|err| anyhow!("unable to read mailing: {err}; run presort first"))
+}
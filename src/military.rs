@@ -1,6 +1,7 @@
 use crate::core::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::source::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
 use heck::ToTitleCase;
@@ -20,6 +21,9 @@ use Center::*; // Required for iterating over the enum
 const FLE_PTH: &str = "military.json";
 const FLE_PTH_ADR: &str = "military_adr.json";
 
+/// The source key.
+pub const KEY_MILITARY: &str = "military";
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Military {
     pub name: String,
@@ -35,36 +39,9 @@ impl Military {
         }
     }
 
-    pub async fn load() -> Result<Military> {
-        // Read members file from disk.
-
-        let military = match read_from_file::<Military>(FLE_PTH) {
-            Ok(military_from_disk) => military_from_disk,
-            Err(_) => {
-                let mut military = Military::new();
-
-                let adrs = &fetch_adrs().await?;
-
-                // Fetch members.
-                military.fetch_members_dod().await?;
-                military.fetch_members_oni(adrs).await?;
-                military.fetch_members_usff(adrs).await?;
-
-                // Write file to disk.
-                write_to_file(&military, FLE_PTH)?;
-
-                military
-            }
-        };
-
-        println!("{} military leaders", military.persons.len());
-
-        Ok(military)
-    }
-
     pub async fn fetch_members_dod(&mut self) -> Result<()> {
         let url = "https://www.defense.gov/Contact/Mailing-Addresses/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_MILITARY).await?;
         let document = Html::parse_document(&html);
 
         let selector = Selector::parse("div.address-each").unwrap();
@@ -79,9 +56,9 @@ impl Military {
 
             // Parse person.
             let mut per = Person {
-                name: name_clean(&cur_lnes[0]),
                 ..Default::default()
             };
+            per.set_name(&cur_lnes[0]);
             per.title1.clone_from(&cur_lnes[1].to_uppercase());
             // Clean up title.
             if let Some(idx) = per.title1.find('/') {
@@ -145,7 +122,7 @@ impl Military {
     pub async fn fetch_members_oni(&mut self, adrs: &HashMap<Center, Address>) -> Result<()> {
         // Fetch url.
         let url = "https://www.oni.navy.mil/About/Biographies/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_MILITARY).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -174,10 +151,10 @@ impl Military {
 
                     // Create person.
                     let mut per = Person {
-                        name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&Oni].clone()]),
                         ..Default::default()
                     };
+                    per.set_name(&full_name);
                     if per.name.is_empty() {
                         return Err(anyhow!("name is empty"));
                     }
@@ -196,7 +173,7 @@ impl Military {
 
         for url in urls {
             // Fetch url.
-            let html = fetch_html(url).await?;
+            let html = fetch_html(url, KEY_MILITARY).await?;
             let document = Html::parse_document(&html);
 
             // Select name.
@@ -211,10 +188,10 @@ impl Military {
                 // eprintln!("{}", full_name.trim());
 
                 let mut per = Person {
-                    name: name_clean(&full_name),
                     adrs: Some(vec![adrs[&Usff].clone()]),
                     ..Default::default()
                 };
+                per.set_name(&full_name);
 
                 eprintln!("{}", per);
                 self.persons.push(per);
@@ -226,9 +203,49 @@ impl Military {
 }
 
 /// Fetch, parse, and standardize an address.
+impl Source for Military {
+    fn key(&self) -> &'static str {
+        KEY_MILITARY
+    }
+
+    fn file_path(&self) -> &'static str {
+        FLE_PTH
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn role(&self) -> Role {
+        self.role.clone()
+    }
+
+    fn persons(&self) -> &[Person] {
+        &self.persons
+    }
+
+    fn persons_mut(&mut self) -> &mut Vec<Person> {
+        &mut self.persons
+    }
+
+    /// Fetch members and addresses from network.
+    fn fetch_members(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(async move {
+            let adrs = &fetch_adrs().await?;
+
+            // Fetch members.
+            self.fetch_members_dod().await?;
+            self.fetch_members_oni(adrs).await?;
+            self.fetch_members_usff(adrs).await?;
+
+            Ok(())
+        })
+    }
+}
+
 pub async fn fetch_prs_std_adr(ctr: Center, url: &str) -> Result<Option<Address>> {
     // Fetch html.
-    let html = fetch_html(url).await?;
+    let html = fetch_html(url, KEY_MILITARY).await?;
 
     // Parse html to address lines.
     let adr_lnes_o = prs_adr_lnes(ctr, &html);
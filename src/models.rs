@@ -27,11 +27,29 @@ impl fmt::Display for Role {
 /// A person.
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Person {
+    /// The roster name, cleaned by `name_clean`, such as `J. Correa`.
+    ///
+    /// Roster diffs match people by name and URL, so the name stays as
+    /// it was before name parsing. `name_parts` has the display form.
     pub name: String,
+    /// The name parts, parsed from the full name.
+    ///
+    /// Rosters without name parts are parsed from `name` when read.
+    #[serde(default)]
+    pub name_parts: PersonName,
     pub title1: String,
     pub title2: String,
     pub url: String,
     pub adrs: Option<Vec<Address>>,
+    /// The roster organization name, set when rosters are combined.
+    #[serde(skip)]
+    pub org: String,
+    /// The roster role, set when rosters are combined.
+    #[serde(skip)]
+    pub role: Option<Role>,
+    /// The roster source key, such as `house`, set when rosters are combined.
+    #[serde(skip)]
+    pub src: String,
 }
 impl fmt::Display for Person {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -44,7 +62,7 @@ impl fmt::Display for Person {
 }
 impl PartialEq for Person {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name
+        self.cmp(other) == Ordering::Equal
     }
 }
 impl Eq for Person {}
@@ -55,10 +73,31 @@ impl PartialOrd for Person {
 }
 impl Ord for Person {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.name.cmp(&other.name)
+        let (a, b) = (self.name_parts(), other.name_parts());
+        (a.key(), a.middle_initial()).cmp(&(b.key(), b.middle_initial()))
     }
 }
 impl Person {
+    /// Sets the name and name parts from a full name.
+    pub fn set_name(&mut self, full_name: &str) {
+        self.name_parts = name_parse(full_name);
+        self.name = name_clean(full_name);
+    }
+
+    /// The name parts, parsed from `name` when unset.
+    pub fn name_parts(&self) -> PersonName {
+        if self.name_parts.is_empty() {
+            name_parse(&self.name)
+        } else {
+            self.name_parts.clone()
+        }
+    }
+
+    /// The sort and match key: family name, then preferred given name.
+    pub fn name_key(&self) -> (String, String) {
+        self.name_parts().key()
+    }
+
     pub fn adr_len(&self) -> usize {
         self.adrs
             .as_ref() // Get a reference to the Option<Vec<Address>>
@@ -67,6 +106,94 @@ impl Person {
     }
 }
 
+/// A person's name in parts.
+///
+/// Parsed by `name_parse`, such as `Gen. Charles Q. "CQ" Brown, Jr.`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PersonName {
+    /// A title or rank before the name, such as `Dr.` or `Gen.`.
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub given: String,
+    /// Middle names and initials.
+    #[serde(default)]
+    pub middle: String,
+    #[serde(default)]
+    pub family: String,
+    /// A generational suffix, such as `Jr.` or `III`.
+    #[serde(default)]
+    pub suffix: String,
+    /// A preferred name used instead of the given name.
+    #[serde(default)]
+    pub nickname: String,
+}
+impl fmt::Display for PersonName {
+    /// Writes the preferred name, such as `Lou Correa`.
+    ///
+    /// A nickname replaces the given and middle names.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts = if self.nickname.is_empty() {
+            vec![&self.given, &self.middle, &self.family]
+        } else {
+            vec![&self.nickname, &self.family]
+        };
+        let parts: Vec<&str> = parts
+            .into_iter()
+            .map(|o| o.as_str())
+            .filter(|o| !o.is_empty())
+            .collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+impl PersonName {
+    pub fn is_empty(&self) -> bool {
+        self.given.is_empty() && self.family.is_empty() && self.nickname.is_empty()
+    }
+
+    /// The nickname, or the given name.
+    pub fn preferred(&self) -> &str {
+        if self.nickname.is_empty() {
+            &self.given
+        } else {
+            &self.nickname
+        }
+    }
+
+    /// The preferred name with any suffix, such as `Charles Q. Brown Jr.`.
+    pub fn formal(&self) -> String {
+        if self.suffix.is_empty() {
+            self.to_string()
+        } else {
+            format!("{} {}", self, self.suffix)
+        }
+    }
+
+    /// The sort and match key: lowercase family and preferred given names
+    /// without dots.
+    pub fn key(&self) -> (String, String) {
+        let norm = |s: &str| dot_remove(s.to_lowercase());
+        (norm(&self.family), norm(self.preferred()))
+    }
+
+    /// The lowercase first middle initial, such as `q` for `Charles Q. Brown`.
+    pub fn middle_initial(&self) -> Option<char> {
+        self.middle.chars().next().map(|c| c.to_ascii_lowercase())
+    }
+
+    /// Whether two names may be the same person.
+    ///
+    /// Keys match, and middle initials match when both names have one:
+    /// `Jane Doe` matches `Jane A. Doe`, which does not match `Jane B. Doe`.
+    pub fn is_match(&self, other: &PersonName) -> bool {
+        self.key() == other.key()
+            && match (self.middle_initial(), other.middle_initial()) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            }
+    }
+}
+
 /// A mailing address.
 #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Address {
@@ -112,6 +239,9 @@ impl fmt::Display for AddressList {
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Mailpiece {
     pub name: String,
+    /// The name parts, for forms of address.
+    #[serde(default)]
+    pub name_parts: PersonName,
     pub title1: Option<String>,
     pub title2: Option<String>,
     pub address1: String,
@@ -122,6 +252,15 @@ pub struct Mailpiece {
     pub delivery_point: Option<String>,
     pub barcode: String,
     pub id: u32,
+    /// The recipient's organization, for letter merge fields.
+    #[serde(default)]
+    pub org: Option<String>,
+    /// The recipient's role, for letter merge fields.
+    #[serde(default)]
+    pub role: Option<Role>,
+    /// The recipient's source key, such as `senate`, for forms of address.
+    #[serde(default)]
+    pub src: String,
 }
 impl fmt::Display for Mailpiece {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -142,6 +281,31 @@ impl fmt::Display for Mailpiece {
     }
 }
 
+impl Mailpiece {
+    /// Creates a routing code (zip + delivery point).
+    ///
+    /// The Routing Code field is an optional field, which may contain a
+    /// 5-digit ZIP Code, a 9-digit ZIP+4 code, or an 11-digit delivery
+    /// point code. When used on letters for automation-rate eligibility purposes,
+    /// the routing code must contain a delivery point code from CASS-certified
+    /// software that accurately matches the delivery address.
+    /// From "Intelligent Mail Barcode Technical Resource Guide" PDF.
+    /// See https://postalpro.usps.com/node/221.
+    pub fn routing_code(&self) -> String {
+        let mut ret = if self.zip4 != 0 {
+            format!("{:05}{:04}", self.zip5, self.zip4)
+        } else {
+            format!("{:05}", self.zip5)
+        };
+        if self.zip4 != 0 {
+            if let Some(delivery_point) = &self.delivery_point {
+                ret.push_str(delivery_point);
+            }
+        }
+        ret
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Letter {
     pub to: String,
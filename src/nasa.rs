@@ -1,6 +1,7 @@
 use crate::core::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::source::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
 use reqwest::Client;
@@ -19,6 +20,9 @@ use Center::*;
 const FLE_PTH: &str = "nasa.json";
 const FLE_PTH_ADR: &str = "nasa_adr.json";
 
+/// The source key.
+pub const KEY_NASA: &str = "nasa";
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Nasa {
     pub name: String,
@@ -35,53 +39,9 @@ impl Nasa {
         }
     }
 
-    pub async fn load() -> Result<Nasa> {
-        // Read file from disk.
-        let mut nasa = match read_from_file::<Nasa>(FLE_PTH) {
-            Ok(nasa_from_disk) => nasa_from_disk,
-            Err(_) => {
-                let mut nasa = Nasa::new();
-
-                let adrs = &fetch_adrs().await?;
-
-                // Fetch members.
-                nasa.persons.extend(nasa.fetch_members_hq(adrs).await?);
-
-                // Directorates
-                nasa.persons.extend(nasa.fetch_members_armd(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_esdmd(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_stmd(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_somd(adrs).await?);
-
-                // Centers
-                nasa.persons.extend(nasa.fetch_members_ames_1(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_ames_2(adrs).await?);
-                nasa.persons
-                    .extend(nasa.fetch_members_ames_science_staff(adrs).await?);
-                nasa.persons
-                    .extend(nasa.fetch_members_armstrong(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_glenn(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_goddard(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_johnson(adrs).await?);
-
-                // nasa.persons.sort_unstable();
-                nasa.persons.dedup_by(|a, b| a == b);
-
-                // Write file to disk.
-                write_to_file(&nasa, FLE_PTH)?;
-
-                nasa
-            }
-        };
-
-        println!("{} scientific leaders", nasa.persons.len());
-
-        Ok(nasa)
-    }
-
     pub async fn fetch_members_hq(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/organization";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -113,11 +73,17 @@ impl Nasa {
                         continue;
                     }
                     //eprintln!("{}", full_name.trim());
+                    let title = row_elm
+                        .select(&title_sel)
+                        .next()
+                        .map(|elm| elm.text().collect::<String>())
+                        .unwrap_or_default();
                     let mut per = Person {
-                        name: name_clean(&full_name),
+                        title1: nbsp_replace(title).trim().into(),
                         adrs: Some(vec![adrs[&HQ].clone()]),
                         ..Default::default()
                     };
+                    per.set_name(&full_name);
 
                     eprintln!("{}", per);
                     pers.push(per);
@@ -130,7 +96,7 @@ impl Nasa {
 
     pub async fn fetch_members_armd(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/directorates/armd/aeronautics-leadership/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -159,10 +125,10 @@ impl Nasa {
                     let full_name = elm.text().collect::<String>();
                     //eprintln!("{}", full_name.trim());
                     let mut per = Person {
-                        name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&HQ].clone()]),
                         ..Default::default()
                     };
+                    per.set_name(&full_name);
 
                     eprintln!("{}", per);
                     pers.push(per);
@@ -178,7 +144,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/exploration-systems-development-mission-directorate/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -207,10 +173,10 @@ impl Nasa {
                     let full_name = elm.text().collect::<String>();
                     //eprintln!("{}", full_name.trim());
                     let mut per = Person {
-                        name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&HQ].clone()]),
                         ..Default::default()
                     };
+                    per.set_name(&full_name);
 
                     eprintln!("{}", per);
                     pers.push(per);
@@ -223,7 +189,7 @@ impl Nasa {
 
     pub async fn fetch_members_stmd(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/about-stmd/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -248,10 +214,10 @@ impl Nasa {
                     let full_name = elm.text().collect::<String>();
                     //eprintln!("{}", full_name.trim());
                     let mut per = Person {
-                        name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&HQ].clone()]),
                         ..Default::default()
                     };
+                    per.set_name(&full_name);
 
                     eprintln!("{}", per);
                     pers.push(per);
@@ -264,7 +230,7 @@ impl Nasa {
 
     pub async fn fetch_members_somd(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/directorates/space-operations/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -293,10 +259,10 @@ impl Nasa {
                     let full_name = elm.text().collect::<String>();
                     //eprintln!("{}", full_name.trim());
                     let mut per = Person {
-                        name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&HQ].clone()]),
                         ..Default::default()
                     };
+                    per.set_name(&full_name);
 
                     eprintln!("{}", per);
                     pers.push(per);
@@ -312,7 +278,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/ames/ames-leadership-organizations/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -327,9 +293,9 @@ impl Nasa {
             }
             //eprintln!("{}", full_name.trim());
             let mut per = Person {
-                name: name_clean(&full_name),
                 ..Default::default()
             };
+            per.set_name(&full_name);
             per.adrs = Some(vec![adrs[&Ames].clone()]);
 
             eprintln!("{}", per);
@@ -344,7 +310,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/ames/science/management-support/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -360,10 +326,10 @@ impl Nasa {
                     let full_name = elm.text().collect::<String>();
                     //eprintln!("{}", full_name.trim());
                     let mut per = Person {
-                        name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&Ames].clone()]),
                         ..Default::default()
                     };
+                    per.set_name(&full_name);
 
                     eprintln!("{}", per);
                     pers.push(per);
@@ -393,7 +359,7 @@ impl Nasa {
             "https://www.nasa.gov/space-science-and-astrobiology-at-ames/who-we-are/members-stx/",
         ];
         for url in urls {
-            let html = fetch_html(url).await?;
+            let html = fetch_html(url, KEY_NASA).await?;
             let document = Html::parse_document(&html);
 
             // Define the CSS selector for the members list.
@@ -408,10 +374,10 @@ impl Nasa {
                         let full_name = elm.text().collect::<String>();
                         //eprintln!("{}", full_name.trim());
                         let mut per = Person {
-                            name: name_clean(&full_name),
                             adrs: Some(vec![adrs[&Ames].clone()]),
                             ..Default::default()
                         };
+                        per.set_name(&full_name);
 
                         eprintln!("{}", per);
                         pers.push(per);
@@ -428,7 +394,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/armstrong/people/leadership-organizations/#center-director";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -447,10 +413,10 @@ impl Nasa {
                         let full_name = elm.text().collect::<String>();
                         //eprintln!("{}", full_name.trim());
                         let mut per = Person {
-                            name: name_clean(&full_name),
                             adrs: Some(vec![adrs[&Armstrong].clone()]),
                             ..Default::default()
                         };
+                        per.set_name(&full_name);
                         eprintln!("{}", per);
                         pers.push(per);
                     }
@@ -466,7 +432,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/about-glenn-research-center/nasa-glenn-leadership/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -482,10 +448,10 @@ impl Nasa {
                     let full_name = elm.text().collect::<String>();
                     //eprintln!("{}", full_name.trim());
                     let mut per = Person {
-                        name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&Glenn].clone()]),
                         ..Default::default()
                     };
+                    per.set_name(&full_name);
 
                     eprintln!("{}", per);
                     pers.push(per);
@@ -501,7 +467,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/goddard/about/#leadership";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -513,10 +479,10 @@ impl Nasa {
             let full_name = elm.text().collect::<String>();
             //eprintln!("{}", full_name.trim());
             let mut per = Person {
-                name: name_clean(&full_name),
                 adrs: Some(vec![adrs[&Goddard].clone()]),
                 ..Default::default()
             };
+            per.set_name(&full_name);
 
             eprintln!("{}", per);
             pers.push(per);
@@ -530,7 +496,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/johnson/#leadership";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, KEY_NASA).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -557,10 +523,10 @@ impl Nasa {
                     //eprintln!("{}", full_name.trim());
                     let full_name = full_name.split_terminator(',').next().unwrap_or_default();
                     let mut per = Person {
-                        name: name_clean(full_name),
                         adrs: Some(vec![adrs[&Johnson].clone()]),
                         ..Default::default()
                     };
+                    per.set_name(full_name);
 
                     eprintln!("{}", per);
                     pers.push(per);
@@ -572,6 +538,66 @@ impl Nasa {
     }
 }
 
+impl Source for Nasa {
+    fn key(&self) -> &'static str {
+        KEY_NASA
+    }
+
+    fn file_path(&self) -> &'static str {
+        FLE_PTH
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn role(&self) -> Role {
+        self.role.clone()
+    }
+
+    fn persons(&self) -> &[Person] {
+        &self.persons
+    }
+
+    fn persons_mut(&mut self) -> &mut Vec<Person> {
+        &mut self.persons
+    }
+
+    /// Fetch members and addresses from network.
+    fn fetch_members(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(async move {
+            let adrs = &fetch_adrs().await?;
+
+            // Fetch members.
+            self.persons.extend(self.fetch_members_hq(adrs).await?);
+
+            // Directorates
+            self.persons.extend(self.fetch_members_armd(adrs).await?);
+            self.persons.extend(self.fetch_members_esdmd(adrs).await?);
+            self.persons.extend(self.fetch_members_stmd(adrs).await?);
+            self.persons.extend(self.fetch_members_somd(adrs).await?);
+
+            // Centers
+            self.persons.extend(self.fetch_members_ames_1(adrs).await?);
+            self.persons.extend(self.fetch_members_ames_2(adrs).await?);
+            self.persons
+                .extend(self.fetch_members_ames_science_staff(adrs).await?);
+            self.persons
+                .extend(self.fetch_members_armstrong(adrs).await?);
+            self.persons.extend(self.fetch_members_glenn(adrs).await?);
+            self.persons.extend(self.fetch_members_goddard(adrs).await?);
+            self.persons.extend(self.fetch_members_johnson(adrs).await?);
+
+            // self.persons.sort_unstable();
+            // Person equality needs the same middle initial, so names with
+            // and without one stay apart.
+            self.persons.dedup_by(|a, b| a == b);
+
+            Ok(())
+        })
+    }
+}
+
 pub async fn fetch_adrs() -> Result<HashMap<Center, Address>> {
     // Read file from disk.
     let mut map_adrs = match read_from_file::<HashMap<Center, Address>>(FLE_PTH_ADR) {
@@ -613,7 +639,7 @@ pub async fn fetch_adrs() -> Result<HashMap<Center, Address>> {
 /// Fetch, parse, and standardize an address.
 pub async fn fetch_prs_std_adr(ctr: Center, url: &str) -> Result<Option<Address>> {
     // Fetch html.
-    let html = fetch_html(url).await?;
+    let html = fetch_html(url, KEY_NASA).await?;
 
     // Parse html to address lines.
     let adr_lnes_o = prs_adr_lnes(ctr, &html);
@@ -1,6 +1,7 @@
 use crate::core::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::source::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
 use reqwest::Client;
@@ -13,6 +14,9 @@ use std::path::Path;
 
 const FLE_PTH: &str = "observer.json";
 
+/// The source key.
+pub const KEY_OBSERVER: &str = "observer";
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Observer {
     pub name: String,
@@ -27,8 +31,39 @@ impl Observer {
             persons: Vec::new(),
         }
     }
-    pub async fn load() -> Result<Observer> {
-        // Read file from disk.
-        read_from_file::<Observer>(FLE_PTH)
+}
+
+impl Source for Observer {
+    fn key(&self) -> &'static str {
+        KEY_OBSERVER
+    }
+
+    fn file_path(&self) -> &'static str {
+        FLE_PTH
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn role(&self) -> Role {
+        self.role.clone()
+    }
+
+    fn persons(&self) -> &[Person] {
+        &self.persons
+    }
+
+    fn persons_mut(&mut self) -> &mut Vec<Person> {
+        &mut self.persons
+    }
+
+    /// Observers are edited by hand.
+    fn fetch_members(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(async { Err(anyhow!("observer roster is edited by hand")) })
+    }
+
+    fn is_manual(&self) -> bool {
+        true
     }
 }
@@ -1,14 +1,19 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use lopdf::{dictionary, Document, Object, ObjectId, Stream};
 
-use crate::{fetch_pdf, numfmt, Mailing, CFG};
+use crate::cache::KEY_USPS;
+use crate::field_map::*;
+use crate::mailing::{BarcodeId, MailingCfg};
+use crate::{fetch_pdf, is_dry_run, Mailing, CFG};
 
 /// Struct representing a PDF document.
 pub struct PostageStatement {
     doc: Document,
     font_id: Option<ObjectId>,
+    map: FieldMap,
 }
 
 impl PostageStatement {
@@ -16,186 +21,166 @@ impl PostageStatement {
     ///
     /// # Arguments
     /// * `pth` - The path to the input PDF document.
+    /// * `map` - The field map for the form edition.
     ///
     /// # Returns
     /// A `PostageStatement` instance.
-    pub fn new<P>(pth: P) -> Result<Self>
+    pub fn new<P>(pth: P, map: FieldMap) -> Result<Self>
     where
         P: AsRef<Path>,
     {
         let doc = Document::load(pth)?;
-        Ok(PostageStatement { doc, font_id: None })
+        Ok(PostageStatement {
+            doc,
+            font_id: None,
+            map,
+        })
     }
 
     /// Loads a new Postage Statement document from a PDF file.
     ///
     /// This function can be used to load a Postage Statement document from a local file or a remote URL.
+    /// The form URL and field positions come from the configured field map,
+    /// which configuration validation matches to the mail class's statement.
     ///
     /// # Returns
     /// A `PostageStatement` instance if the loading is successful, otherwise an error.
     pub async fn load_new() -> Result<Self> {
-        let pth = fetch_pdf("https://about.usps.com/forms/ps3602n.pdf").await?;
-        Self::new(pth)
+        let map = FieldMap::load(&CFG.ps.field_map, CFG.mail_class.statement())?;
+        let pth = fetch_pdf(&map.url, KEY_USPS).await?;
+        Self::new(pth, map)
     }
 
     /// Fill in the postage statement and save the file.
+    ///
+    /// Fills AcroForm fields by their `acro_name` when the form has them.
+    /// Otherwise writes text at the field map positions.
     pub fn fill_and_save(&mut self, mailing: &Mailing, mut pth: PathBuf) -> Result<()> {
-        // Get page IDs.
-        let pg1_id = self.get_page_id(0)?;
-        let pg2_id = self.get_page_id(1)?;
-
-        // Add address.
-        let mut fnt_sze = 9.0;
-        let x = 60.0;
-        let mut y = 698.0;
-        let y_dlt = fnt_sze + (0.2 * fnt_sze);
-        self.add_text_to_pdf(pg1_id, &CFG.ps.adr.name, x, y, fnt_sze)?;
-        y -= y_dlt;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.adr.address1, x, y, fnt_sze)?;
-        y -= y_dlt;
-        self.add_text_to_pdf(
-            pg1_id,
-            &format!(
-                "{}, {} {}-{}",
-                &CFG.ps.adr.city, &CFG.ps.adr.state, &CFG.ps.adr.zip5, &CFG.ps.adr.zip4
-            ),
-            x,
-            y,
-            fnt_sze,
-        )?;
-
-        // Add email and phone.
-        let x = 170.0;
-        let mut y = 698.0;
-        fnt_sze = 8.0;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.email, x, y, fnt_sze)?;
-        y -= y_dlt;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.phone, x, y, fnt_sze)?;
-
-        // Add nonprofit auth.
-        let x = 188.0;
-        let y = 666.0;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.nonprofit_auth_id, x, y, fnt_sze)?;
-
-        // Add EPS account number..
-        let x = 122.0;
-        let y = 648.0;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.eps_id, x, y, fnt_sze)?;
-
-        // Add CRID.
-        let x = 210.0;
-        let y = 648.0;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.crid, x, y, fnt_sze)?;
-
-        // Post Office of Mailing.
-        let x = 60.0;
-        let y = 620.0;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.post_office_mailing, x, y, fnt_sze)?;
-
-        // Mailing Date.
-        let x = 185.0;
-        let y = 620.0;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.mailing_date, x, y, fnt_sze)?;
-
-        // Total # of Pieces.
-        let x = 310.0;
-        let y = 595.1;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(
-            pg1_id,
-            &numfmt(mailing.mailpiece_cnt as usize),
-            x,
-            y,
-            fnt_sze,
-        )?;
-
-        // Statement Seq. No.
-        let x = 365.0;
-        let y = 620.0;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(
-            pg1_id,
-            &format!("{:03}", CFG.ps.last_statement_id + 1),
-            x,
-            y,
-            fnt_sze,
-        )?;
-
-        // 1 ft. Letter Trays.
-        let x = 529.0;
-        let y = 597.0;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &mailing.tray_1ft_cnt.to_string(), x, y, fnt_sze)?;
-
-        // 1 ft. Letter Trays.
-        let y = 573.0;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &mailing.tray_2ft_cnt.to_string(), x, y, fnt_sze)?;
-
-        // Permit Imprint ID.
-        let x = 365.0;
-        let y = 571.0;
-        fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.indicia.permit_id, x, y, fnt_sze)?;
-
-        // Type of Postage.
-        let x = 56.0;
-        let y = 595.1;
-        fnt_sze = 12.0;
-        self.add_text_to_pdf(pg1_id, "X", x, y, fnt_sze)?;
-
-        // Processing Category.
-        let x = 130.5;
-        let y = 595.1;
-        fnt_sze = 12.0;
-        self.add_text_to_pdf(pg1_id, "X", x, y, fnt_sze)?;
-
-        // Move Update Method.
-        let x = 130.5;
-        let y = 518.0;
-        fnt_sze = 12.0;
-        self.add_text_to_pdf(pg1_id, "X", x, y, fnt_sze)?;
-
-        // Combined Mailing.
-        let x = 130.5;
-        let y = 483.5;
-        fnt_sze = 12.0;
-        self.add_text_to_pdf(pg1_id, "X", x, y, fnt_sze)?;
-
-        // Part A.
-        let x = 181.5;
-        let y = 471.0;
-        fnt_sze = 12.0;
-        self.add_text_to_pdf(pg1_id, "X", x, y, fnt_sze)?;
-
-        // Three Nos.
-        let x = 414.1;
-        let y = 512.0;
-        fnt_sze = 12.0;
-        self.add_text_to_pdf(pg1_id, "X", x, y, fnt_sze)?;
-        let y = 498.0;
-        self.add_text_to_pdf(pg1_id, "X", x, y, fnt_sze)?;
-        let y = 485.0;
-        self.add_text_to_pdf(pg1_id, "X", x, y, fnt_sze)?;
-
-        // Page two.
-
-        // // 5-digit No. of Pieces.
-        // let x = 181.5;
-        // let y = 471.0;
-        // fnt_sze = 12.0;
-        // self.add_text_to_pdf(pg2_id, "X", x, y, fnt_sze)?;
+        let vals = field_values(mailing, &CFG);
+        let map = self.map.clone();
+        let fields = self.form_fields()?;
+        if !fields.is_empty() {
+            self.set_need_appearances()?;
+        }
+
+        for (spec, txt) in map.fill(&vals)? {
+            if let Some(field_id) = spec.acro_name.as_ref().and_then(|o| fields.get(o)) {
+                self.set_field(*field_id, &txt)?;
+                continue;
+            }
+            let pg_idx = spec
+                .page
+                .checked_sub(1)
+                .ok_or(anyhow!("invalid page for field {}", spec.name))?;
+            let pg_id = self.get_page_id(pg_idx)?;
+            self.add_text_to_pdf(pg_id, &txt, spec.x, spec.y, spec.font_size)?;
+        }
 
+        if is_dry_run() {
+            eprintln!("Dry run: skipping postage statement");
+            return Ok(());
+        }
 
         pth.push("_postage_statement");
         pth.set_extension("pdf");
-        self.save(pth);
+        self.save(&pth)
+            .map_err(|err| anyhow!("unable to save {:?}: {}", pth, err))?;
+
+        Ok(())
+    }
+
+    /// Returns AcroForm fields by fully qualified name, such as `parent.child`.
+    ///
+    /// Empty when the form has no fillable fields.
+    pub fn form_fields(&self) -> Result<BTreeMap<String, ObjectId>> {
+        let mut ret = BTreeMap::new();
+        let Ok(acro_form) = self.doc.catalog()?.get_deref(b"AcroForm", &self.doc) else {
+            return Ok(ret);
+        };
+        let Ok(fields) = acro_form.as_dict()?.get_deref(b"Fields", &self.doc) else {
+            return Ok(ret);
+        };
+        for field in fields.as_array()? {
+            self.add_form_field(field.as_reference()?, "", &mut ret)?;
+        }
+        Ok(ret)
+    }
+
+    /// Adds a field, or its named kids, to a map of fields.
+    fn add_form_field(
+        &self,
+        id: ObjectId,
+        prefix: &str,
+        fields: &mut BTreeMap<String, ObjectId>,
+    ) -> Result<()> {
+        let dict = self.doc.get_object(id)?.as_dict()?;
+        let name = match dict.get(b"T").and_then(|o| o.as_str()) {
+            Ok(t) if prefix.is_empty() => text_string(t),
+            Ok(t) => format!("{}.{}", prefix, text_string(t)),
+            Err(_) => prefix.to_string(),
+        };
+
+        // Kids without a name are widgets of this field.
+        let kids: Vec<ObjectId> = match dict.get(b"Kids").and_then(|o| o.as_array()) {
+            Ok(kids) => kids.iter().filter_map(|o| o.as_reference().ok()).collect(),
+            Err(_) => Vec::new(),
+        };
+        let named: Vec<ObjectId> = kids
+            .into_iter()
+            .filter(|kid| {
+                self.doc
+                    .get_object(*kid)
+                    .and_then(|o| o.as_dict())
+                    .is_ok_and(|o| o.has(b"T"))
+            })
+            .collect();
+        for kid in named.iter() {
+            self.add_form_field(*kid, &name, fields)?;
+        }
+        if named.is_empty() {
+            fields.insert(name, id);
+        }
+        Ok(())
+    }
+
+    /// Sets the value of a form field.
+    ///
+    /// A check box is set to its on state.
+    pub fn set_field(&mut self, id: ObjectId, txt: &str) -> Result<()> {
+        let dict = self.doc.get_object(id)?.as_dict()?;
+        if dict.get(b"FT").and_then(|o| o.as_name()).ok() != Some(b"Btn".as_slice()) {
+            let dict = self.doc.get_object_mut(id)?.as_dict_mut()?;
+            dict.set("V", Object::string_literal(txt));
+            return Ok(());
+        }
+
+        // Find the on state in the normal appearance.
+        let on = dict
+            .get_deref(b"AP", &self.doc)
+            .and_then(|o| o.as_dict())
+            .and_then(|o| o.get_deref(b"N", &self.doc))
+            .and_then(|o| o.as_dict())
+            .ok()
+            .and_then(|o| o.iter().map(|(k, _)| k.clone()).find(|k| k != b"Off"))
+            .unwrap_or(b"Yes".to_vec());
+        let dict = self.doc.get_object_mut(id)?.as_dict_mut()?;
+        dict.set("V", Object::Name(on.clone()));
+        dict.set("AS", Object::Name(on));
+        Ok(())
+    }
 
+    /// Asks PDF viewers to draw filled-in field values.
+    fn set_need_appearances(&mut self) -> Result<()> {
+        let acro_form = self.doc.catalog()?.get(b"AcroForm")?.clone();
+        let dict = match acro_form {
+            Object::Reference(id) => self.doc.get_object_mut(id)?.as_dict_mut()?,
+            _ => self
+                .doc
+                .catalog_mut()?
+                .get_mut(b"AcroForm")?
+                .as_dict_mut()?,
+        };
+        dict.set("NeedAppearances", true);
         Ok(())
     }
 
@@ -298,3 +283,140 @@ impl PostageStatement {
         Ok(())
     }
 }
+
+/// Returns postage statement values by field map value key.
+pub fn field_values(mailing: &Mailing, cfg: &MailingCfg) -> FieldValues {
+    let txt = |s: &str| FieldValue::Text(s.into());
+    let cnt = |n: usize| FieldValue::Cnt(n);
+    let amt = |a: f64| FieldValue::Amt(a);
+    let adr = &cfg.ps.adr;
+    let city_state_zip = format!("{}, {} {}-{}", adr.city, adr.state, adr.zip5, adr.zip4);
+    [
+        ("ps.adr.name", txt(&adr.name)),
+        ("ps.adr.address1", txt(&adr.address1)),
+        ("ps.adr.city_state_zip", txt(&city_state_zip)),
+        ("ps.email", txt(&cfg.ps.email)),
+        ("ps.phone", txt(&cfg.ps.phone)),
+        ("ps.post_office_mailing", txt(&cfg.ps.post_office_mailing)),
+        ("ps.mailing_date", txt(&cfg.ps.mailing_date)),
+        ("nonprofit_auth_id", txt(&cfg.nonprofit_auth_id)),
+        ("eps_id", txt(&cfg.eps_id)),
+        ("crid", txt(&cfg.crid)),
+        ("indicia.permit_id", txt(&cfg.indicia.permit_id)),
+        ("statement_seq", cnt(cfg.ps.last_statement_id as usize + 1)),
+        ("mailpiece_cnt", cnt(mailing.mailpiece_cnt as usize)),
+        ("tray_1ft_cnt", cnt(mailing.tray_1ft_cnt as usize)),
+        ("tray_2ft_cnt", cnt(mailing.tray_2ft_cnt as usize)),
+        ("five_dig_cnt", cnt(mailing.five_dig_cnt as usize)),
+        ("aadc_cnt", cnt(mailing.aadc_cnt as usize)),
+        ("mixed_aadc_cnt", cnt(mailing.mixed_aadc_cnt as usize)),
+        ("prc_five_dig", amt(mailing.price(BarcodeId::FiveDigit))),
+        ("prc_aadc", amt(mailing.price(BarcodeId::Aadc))),
+        ("prc_mixed_aadc", amt(mailing.price(BarcodeId::MixedAadc))),
+        (
+            "postage_subtotal_five_dig",
+            amt(mailing.postage_subtotal_five_dig),
+        ),
+        ("postage_subtotal_aadc", amt(mailing.postage_subtotal_aadc)),
+        (
+            "postage_subtotal_mixed_aadc",
+            amt(mailing.postage_subtotal_mixed_aadc),
+        ),
+        ("part_a_subtotal", amt(mailing.part_a_subtotal)),
+        ("yes", FieldValue::Flag(true)),
+    ]
+    .into_iter()
+    .map(|(key, val)| (key.to_string(), val))
+    .collect()
+}
+
+/// Decodes a PDF text string, which is UTF-16BE when it starts with a byte order mark.
+fn text_string(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => String::from_utf16_lossy(
+            &utf16
+                .chunks_exact(2)
+                .map(|o| u16::from_be_bytes([o[0], o[1]]))
+                .collect::<Vec<u16>>(),
+        ),
+        None => String::from_utf8_lossy(bytes).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rates::MailClass;
+
+    #[test]
+    fn test_field_map_values() {
+        // Every value in each built-in field map resolves.
+        let mut mailing = Mailing::new();
+        mailing.mailpiece_cnt = 300;
+        for mail_class in [
+            MailClass::Nonprofit,
+            MailClass::Marketing,
+            MailClass::FirstClass,
+        ] {
+            let map = FieldMap::load("", mail_class.statement()).unwrap();
+            let txts = map
+                .fill(&field_values(&mailing, &MailingCfg::default()))
+                .unwrap();
+            let names: Vec<&str> = txts.iter().map(|(spec, _)| spec.name.as_str()).collect();
+            assert!(names.contains(&"total_pieces"), "{mail_class}");
+
+            // Only page 1 is filled.
+            assert!(map.fields.iter().all(|o| o.page == 1), "{mail_class}");
+        }
+    }
+
+    #[test]
+    fn test_form_fields() {
+        let mut doc = Document::with_version("1.5");
+        let crid_id = doc.add_object(dictionary! {
+            "T" => Object::string_literal("crid"),
+            "FT" => "Tx",
+        });
+        let box_id = doc.add_object(dictionary! {
+            "T" => Object::string_literal("b"),
+            "FT" => "Btn",
+            "AP" => dictionary! {
+                "N" => dictionary! { "Off" => Object::Null, "On" => Object::Null },
+            },
+        });
+        let parent_id = doc.add_object(dictionary! {
+            "T" => Object::string_literal("a"),
+            "Kids" => vec![Object::Reference(box_id)],
+        });
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "AcroForm" => dictionary! {
+                "Fields" => vec![Object::Reference(crid_id), Object::Reference(parent_id)],
+            },
+        });
+        doc.trailer.set("Root", catalog_id);
+        let mut ps = PostageStatement {
+            doc,
+            font_id: None,
+            map: FieldMap::load("", "PS Form 3602-N").unwrap(),
+        };
+
+        let fields = ps.form_fields().unwrap();
+        let names: Vec<&str> = fields.keys().map(|o| o.as_str()).collect();
+        assert_eq!(names, vec!["a.b", "crid"]);
+
+        ps.set_field(fields["crid"], "1234").unwrap();
+        ps.set_field(fields["a.b"], "X").unwrap();
+        ps.set_need_appearances().unwrap();
+        let crid = ps.doc.get_object(crid_id).unwrap().as_dict().unwrap();
+        assert_eq!(crid.get(b"V").unwrap().as_str().unwrap(), b"1234");
+        let check_box = ps.doc.get_object(box_id).unwrap().as_dict().unwrap();
+        assert_eq!(check_box.get(b"AS").unwrap().as_name().unwrap(), b"On");
+    }
+
+    #[test]
+    fn test_text_string() {
+        assert_eq!(text_string(b"Permit"), "Permit");
+        assert_eq!(text_string(&[0xFE, 0xFF, 0x00, 0x41, 0x00, 0x31]), "A1");
+    }
+}
@@ -31,6 +31,9 @@ pub struct Prsr {
     pub re_name_initials: Regex,
     /// A regex matching name affectations.
     pub re_name_affectation: Regex,
+    /// A regex matching quoted or parenthesized text in a name, such as a
+    /// nickname.
+    pub re_name_nickname: Regex,
 }
 
 impl Prsr {
@@ -127,6 +130,7 @@ impl Prsr {
                     \b                  # Word boundry
                 )
             "#).unwrap(), 
+            re_name_nickname: Regex::new(r#""([^"]*)"|\u{201c}([^\u{201d}]*)\u{201d}|\(([^)]*)\)"#).unwrap(),
         }
     }
 
@@ -887,6 +891,140 @@ pub fn name_clean(full_name: &str) -> String {
     s.trim().trim_end_matches(',').trim().replace("  ", " ")
 }
 
+/// Name prefixes and military ranks.
+///
+/// Multiword prefixes come first so "Lt. Gen." matches before "Lt.".
+pub const NAME_PREFIXES: [&str; 32] = [
+    "The Honorable",
+    "Lieutenant General",
+    "Major General",
+    "Brigadier General",
+    "Vice Admiral",
+    "Rear Admiral",
+    "Lt. Gen.",
+    "Maj. Gen.",
+    "Brig. Gen.",
+    "Vice Adm.",
+    "Rear Adm.",
+    "Lt. Col.",
+    "General",
+    "Gen.",
+    "Admiral",
+    "Adm.",
+    "Col.",
+    "Capt.",
+    "Cmdr.",
+    "FLTCM.",
+    "LTG",
+    "VADM",
+    "RADM",
+    "Hon.",
+    "Gov.",
+    "Sen.",
+    "Rep.",
+    "Mrs.",
+    "Mr.",
+    "Ms.",
+    "Dr.",
+    "Dr",
+];
+
+/// Generational name suffixes.
+const NAME_SUFFIXES: [&str; 5] = ["Jr.", "Sr.", "II", "III", "IV"];
+
+/// Family name particles, such as in "Van Hollen".
+const NAME_PARTICLES: [&str; 11] = [
+    "da", "de", "del", "della", "di", "du", "la", "le", "st", "van", "von",
+];
+
+/// Parenthesized words that are not nicknames.
+const NAME_NOT_NICKNAMES: [&str; 4] = ["Acting", "Interim", "Ret", "Retired"];
+
+/// Parses a full name into parts.
+///
+/// Finds the prefix, a quoted or parenthesized one-word nickname and a
+/// generational suffix, then drops degrees with `name_clean`. The family
+/// name is the last word with any particles before it.
+pub fn name_parse(full_name: &str) -> PersonName {
+    let mut ret = PersonName::default();
+    let s = rht_quo_replace(nbsp_replace(full_name.to_string()));
+
+    // Find the nickname, then drop quoted and parenthesized text.
+    if let Some(caps) = PRSR.re_name_nickname.captures(&s) {
+        let nick = (1..=3)
+            .find_map(|idx| caps.get(idx))
+            .map_or("", |o| o.as_str())
+            .trim();
+        if !nick.is_empty()
+            && !nick.contains(char::is_whitespace)
+            && nick.starts_with(char::is_uppercase)
+            && !NAME_NOT_NICKNAMES.contains(&nick.trim_end_matches('.'))
+        {
+            ret.nickname = nick.into();
+        }
+    }
+    let s = PRSR.re_name_nickname.replace_all(&s, " ");
+
+    // Find prefixes.
+    let mut rest = s.trim();
+    let mut prefixes = Vec::new();
+    while let Some(prefix) = NAME_PREFIXES.iter().find(|o| {
+        rest.get(..o.len())
+            .is_some_and(|pre| pre.eq_ignore_ascii_case(o))
+            && rest[o.len()..].starts_with(' ')
+    }) {
+        prefixes.push(*prefix);
+        rest = rest[prefix.len()..].trim_start();
+    }
+    ret.prefix = prefixes.join(" ");
+
+    // Find a suffix after the first word.
+    let mut words: Vec<&str> = rest
+        .split_whitespace()
+        .map(|o| o.trim_matches(','))
+        .filter(|o| !o.is_empty())
+        .collect();
+    if let Some(idx) = (1..words.len()).find(|idx| name_suffix(words[*idx]).is_some()) {
+        ret.suffix = name_suffix(words.remove(idx)).unwrap_or_default().into();
+    }
+
+    // Drop degrees and split given, middle and family names.
+    let s = name_clean(&words.join(" "));
+    let words: Vec<&str> = s
+        .split_whitespace()
+        .map(|o| o.trim_matches(','))
+        .filter(|o| !o.is_empty())
+        .collect();
+    match words.len() {
+        0 => {}
+        1 => ret.family = words[0].into(),
+        len => {
+            let mut idx = len - 1;
+            while idx > 1
+                && NAME_PARTICLES
+                    .iter()
+                    .any(|o| o.eq_ignore_ascii_case(words[idx - 1].trim_end_matches('.')))
+            {
+                idx -= 1;
+            }
+            ret.given = words[0].into();
+            ret.middle = words[1..idx].join(" ");
+            ret.family = words[idx..].join(" ");
+        }
+    }
+
+    ret
+}
+
+/// The standard form of a generational suffix, such as `Jr.` for `JR`.
+fn name_suffix(word: &str) -> Option<&'static str> {
+    let word = word.trim_end_matches('.');
+    NAME_SUFFIXES.into_iter().find(|o| match *o {
+        "Jr." | "Sr." => o.trim_end_matches('.').eq_ignore_ascii_case(word),
+        _ => *o == word,
+    })
+}
+
 pub fn name_clean_split(full_name: &str) -> (String, String) {
     // Support two-word last names.
     // "John Quincy Public"
@@ -1904,6 +2042,134 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_name_parse() {
+        let nme = |prefix: &str,
+                   given: &str,
+                   middle: &str,
+                   family: &str,
+                   suffix: &str,
+                   nickname: &str| {
+            PersonName {
+                prefix: prefix.into(),
+                given: given.into(),
+                middle: middle.into(),
+                family: family.into(),
+                suffix: suffix.into(),
+                nickname: nickname.into(),
+            }
+        };
+        let cases = vec![
+            ("Jane Doe", nme("", "Jane", "", "Doe", "", ""), "Jane Doe"),
+            (
+                "Gen. Charles Q. Brown, Jr.",
+                nme("Gen.", "Charles", "Q.", "Brown", "Jr.", ""),
+                "Charles Q. Brown",
+            ),
+            (
+                "Robert C. (Bobby) Scott",
+                nme("", "Robert", "C.", "Scott", "", "Bobby"),
+                "Bobby Scott",
+            ),
+            (
+                "Dr. Jane A. Doe Ph.D.",
+                nme("Dr.", "Jane", "A.", "Doe", "", ""),
+                "Jane A. Doe",
+            ),
+            (
+                "Martin Luther King III",
+                nme("", "Martin", "Luther", "King", "III", ""),
+                "Martin Luther King",
+            ),
+            (
+                "John Smith SR",
+                nme("", "John", "", "Smith", "Sr.", ""),
+                "John Smith",
+            ),
+            (
+                "Chris Van Hollen",
+                nme("", "Chris", "", "Van Hollen", "", ""),
+                "Chris Van Hollen",
+            ),
+            (
+                "Lt. Gen. Jane Doe (Ret.)",
+                nme("Lt. Gen.", "Jane", "", "Doe", "", ""),
+                "Jane Doe",
+            ),
+            (
+                "John Doe (Acting)",
+                nme("", "John", "", "Doe", "", ""),
+                "John Doe",
+            ),
+            (
+                "Gov.\u{a0}Jared Polis",
+                nme("Gov.", "Jared", "", "Polis", "", ""),
+                "Jared Polis",
+            ),
+            (
+                "FLTCM. John Smith",
+                nme("FLTCM.", "John", "", "Smith", "", ""),
+                "John Smith",
+            ),
+            ("Cher", nme("", "", "", "Cher", "", ""), "Cher"),
+            ("", nme("", "", "", "", "", ""), ""),
+            // House roster names with quoted nicknames.
+            (
+                "J. \"Lou\" Correa",
+                nme("", "J.", "", "Correa", "", "Lou"),
+                "Lou Correa",
+            ),
+            (
+                "A. \"Drew\" Ferguson",
+                nme("", "A.", "", "Ferguson", "", "Drew"),
+                "Drew Ferguson",
+            ),
+            (
+                "H. \"Morgan\" Griffith",
+                nme("", "H.", "", "Griffith", "", "Morgan"),
+                "Morgan Griffith",
+            ),
+            (
+                "J. \"French\" Hill",
+                nme("", "J.", "", "Hill", "", "French"),
+                "French Hill",
+            ),
+            (
+                "C. A. \"Dutch\" Ruppersberger",
+                nme("", "C.", "A.", "Ruppersberger", "", "Dutch"),
+                "Dutch Ruppersberger",
+            ),
+            (
+                "W. \"Greg\" Steube",
+                nme("", "W.", "", "Steube", "", "Greg"),
+                "Greg Steube",
+            ),
+        ];
+
+        for (input, expected, display) in cases {
+            let nme = name_parse(input);
+            assert_eq!(nme, expected, "{input}");
+            assert_eq!(nme.to_string(), display, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_name_match() {
+        let cases = vec![
+            ("Jane Doe", "Jane Doe", true),
+            ("Jane Doe", "Jane A. Doe", true),
+            ("Jane A. Doe", "Jane Ann Doe", true),
+            ("Jane A. Doe", "Jane B. Doe", false),
+            ("Jane Doe", "John Doe", false),
+            ("J. \"Lou\" Correa", "Lou Correa", true),
+        ];
+
+        for (a, b, expected) in cases {
+            assert_eq!(name_parse(a).is_match(&name_parse(b)), expected, "{a} {b}");
+            assert_eq!(name_parse(b).is_match(&name_parse(a)), expected, "{a} {b}");
+        }
+    }
+
     #[test]
     fn test_trim_list_prefix() {
         let mut lines = vec![
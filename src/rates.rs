@@ -0,0 +1,362 @@
+use crate::core::*;
+use crate::mailing::*;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Directory of rate table files, such as `rates/marketing_2025-07-13.json`.
+pub const DIR_RATES: &str = "rates";
+
+/// Built-in rate tables.
+///
+/// A table in `DIR_RATES` replaces a built-in table with the same mail class
+/// and effective date.
+static RATES_BUILTIN: &[&str] = &[
+    include_str!("../rates/marketing_nonprofit_2024-07-14.json"),
+    include_str!("../rates/marketing_2024-07-14.json"),
+    include_str!("../rates/first_class_2024-07-14.json"),
+];
+
+/// A USPS mail class and price list.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum MailClass {
+    /// USPS Marketing Mail Nonprofit.
+    #[default]
+    Nonprofit,
+    /// USPS Marketing Mail commercial.
+    Marketing,
+    /// First-Class Mail presorted.
+    FirstClass,
+}
+
+impl MailClass {
+    /// The postage statement form.
+    pub fn statement(&self) -> &'static str {
+        match self {
+            MailClass::Nonprofit => "PS Form 3602-N",
+            MailClass::Marketing => "PS Form 3602-R",
+            MailClass::FirstClass => "PS Form 3600-FCM",
+        }
+    }
+
+    /// The mail class markings printed on envelopes, one per line.
+    ///
+    /// See DMM 604.5.3 permit imprint markings.
+    pub fn markings(&self) -> &'static [&'static str] {
+        match self {
+            MailClass::Nonprofit => &["NONPROFIT", "PRSRT MKTG", "AUTO"],
+            MailClass::Marketing => &["PRSRT MKTG", "AUTO"],
+            MailClass::FirstClass => &["PRSRT FIRST-CLASS", "AUTO"],
+        }
+    }
+
+    /// The class abbreviation for tray content lines.
+    pub fn content_abbr(&self) -> &'static str {
+        match self {
+            MailClass::Nonprofit | MailClass::Marketing => "STD",
+            MailClass::FirstClass => "FCM",
+        }
+    }
+}
+
+impl fmt::Display for MailClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MailClass::Nonprofit => write!(f, "Marketing Mail Nonprofit"),
+            MailClass::Marketing => write!(f, "Marketing Mail"),
+            MailClass::FirstClass => write!(f, "First-Class Mail"),
+        }
+    }
+}
+
+/// Letter piece prices for one mail class, from an effective date.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RateTable {
+    pub mail_class: MailClass,
+    pub effective: NaiveDate,
+    /// Where the prices come from, such as a USPS price list.
+    #[serde(default)]
+    pub source: String,
+    /// Piece prices by presort level.
+    ///
+    /// A table without a 3-digit price charges the AADC price for
+    /// 3-digit trays.
+    pub prices: BTreeMap<BarcodeId, f64>,
+}
+
+/// Postage for one rate category.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PostageLine {
+    pub category: BarcodeId,
+    pub price: f64,
+    pub cnt: u16,
+    pub subtotal: f64,
+}
+
+impl RateTable {
+    /// Returns the rate category and price for a presort level.
+    pub fn price(&self, barcode_id: BarcodeId) -> Result<(BarcodeId, f64)> {
+        let category = match barcode_id {
+            BarcodeId::ThreeDigit if !self.prices.contains_key(&barcode_id) => BarcodeId::Aadc,
+            _ => barcode_id,
+        };
+        self.prices
+            .get(&category)
+            .map(|prc| (category, *prc))
+            .ok_or(anyhow!("no {:?} price in {}", barcode_id, self))
+    }
+
+    /// Breaks down postage for trays by rate category.
+    pub fn postage_lines(&self, trays: &[MailTray]) -> Result<Vec<PostageLine>> {
+        let mut lnes: BTreeMap<BarcodeId, PostageLine> = BTreeMap::new();
+        for tray in trays.iter().filter(|o| !o.mailpieces.is_empty()) {
+            let (category, price) = self.price(tray.barcode_id)?;
+            let lne = lnes.entry(category).or_insert(PostageLine {
+                category,
+                price,
+                cnt: 0,
+                subtotal: 0.0,
+            });
+            lne.cnt += tray.mailpieces.len() as u16;
+        }
+        Ok(lnes
+            .into_values()
+            .map(|mut lne| {
+                lne.subtotal = lne.cnt as f64 * lne.price;
+                lne
+            })
+            .collect())
+    }
+}
+
+impl fmt::Display for RateTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} letters effective {}",
+            self.mail_class, self.effective
+        )
+    }
+}
+
+impl fmt::Display for PostageLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.category {
+            BarcodeId::Default => "default",
+            BarcodeId::CarrierRoute => "carrier route",
+            BarcodeId::FiveDigit => "5-digit",
+            BarcodeId::ThreeDigit => "3-digit",
+            BarcodeId::Aadc => "AADC",
+            BarcodeId::MixedAadc => "mixed AADC",
+        };
+        write!(
+            f,
+            "{} {} x ${:.3} = ${:.2}",
+            name, self.cnt, self.price, self.subtotal
+        )
+    }
+}
+
+/// Built-in and configured rate tables.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RateTables(pub Vec<RateTable>);
+
+impl RateTables {
+    /// Reads the built-in tables and each JSON table in a directory.
+    ///
+    /// A missing directory adds no tables.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<RateTables> {
+        let mut ret = RateTables::default();
+        for txt in RATES_BUILTIN {
+            ret.insert(serde_json::from_str(txt)?);
+        }
+
+        let dir = dir.as_ref();
+        if !dir.exists() {
+            return Ok(ret);
+        }
+        let mut pths: Vec<_> = fs::read_dir(dir)?
+            .map(|dir_ent| dir_ent.map(|dir_ent| dir_ent.path()))
+            .collect::<Result<_, _>>()?;
+        pths.sort_unstable();
+        for pth in pths
+            .iter()
+            .filter(|pth| pth.extension().is_some_and(|ext| ext == "json"))
+        {
+            let table = read_from_file::<RateTable>(&pth.to_string_lossy())
+                .map_err(|err| anyhow!("invalid rate table {:?}: {}", pth, err))?;
+            ret.insert(table);
+        }
+        Ok(ret)
+    }
+
+    /// Adds a table, replacing any table with the same mail class and
+    /// effective date.
+    pub fn insert(&mut self, table: RateTable) {
+        self.0
+            .retain(|o| (o.mail_class, o.effective) != (table.mail_class, table.effective));
+        self.0.push(table);
+    }
+
+    /// Returns the latest table for a mail class in effect on a date.
+    pub fn select(&self, mail_class: MailClass, date: NaiveDate) -> Result<&RateTable> {
+        self.0
+            .iter()
+            .filter(|o| o.mail_class == mail_class && o.effective <= date)
+            .max_by_key(|o| o.effective)
+            .ok_or(anyhow!(
+                "no {} rate table in effect on {}",
+                mail_class,
+                date
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    fn table(
+        mail_class: MailClass,
+        effective: NaiveDate,
+        prices: &[(BarcodeId, f64)],
+    ) -> RateTable {
+        RateTable {
+            mail_class,
+            effective,
+            source: "".into(),
+            prices: prices.iter().cloned().collect(),
+        }
+    }
+
+    fn tray(barcode_id: BarcodeId, cnt: usize) -> MailTray {
+        MailTray {
+            name: "A".into(),
+            size: TraySize::OneFoot,
+            barcode_id,
+            dest: Default::default(),
+            serial: 0,
+            mailpieces: vec![Default::default(); cnt],
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let tables = RateTables(vec![
+            table(MailClass::Nonprofit, date(2024, 7, 14), &[]),
+            table(MailClass::Nonprofit, date(2025, 7, 13), &[]),
+            table(MailClass::Marketing, date(2025, 1, 19), &[]),
+        ]);
+        let cases = vec![
+            (
+                MailClass::Nonprofit,
+                date(2024, 10, 1),
+                Some(date(2024, 7, 14)),
+            ),
+            (
+                MailClass::Nonprofit,
+                date(2025, 7, 13),
+                Some(date(2025, 7, 13)),
+            ),
+            (MailClass::Nonprofit, date(2024, 1, 1), None),
+            (
+                MailClass::Marketing,
+                date(2025, 10, 1),
+                Some(date(2025, 1, 19)),
+            ),
+        ];
+
+        for (mail_class, mailing_date, expected) in cases {
+            let effective = tables
+                .select(mail_class, mailing_date)
+                .ok()
+                .map(|o| o.effective);
+            assert_eq!(effective, expected, "{mail_class} {mailing_date}");
+        }
+    }
+
+    #[test]
+    fn test_postage_lines() {
+        let table = table(
+            MailClass::Nonprofit,
+            date(2024, 7, 14),
+            &[
+                (BarcodeId::FiveDigit, 0.173),
+                (BarcodeId::Aadc, 0.197),
+                (BarcodeId::MixedAadc, 0.208),
+            ],
+        );
+        let trays = vec![
+            tray(BarcodeId::FiveDigit, 200),
+            tray(BarcodeId::FiveDigit, 150),
+            tray(BarcodeId::ThreeDigit, 150),
+            tray(BarcodeId::Aadc, 160),
+            tray(BarcodeId::MixedAadc, 10),
+        ];
+
+        let lnes = table.postage_lines(&trays).unwrap();
+        let cnts: Vec<(BarcodeId, u16)> = lnes.iter().map(|o| (o.category, o.cnt)).collect();
+        // 3-digit pieces take the AADC price.
+        assert_eq!(
+            cnts,
+            vec![
+                (BarcodeId::FiveDigit, 350),
+                (BarcodeId::Aadc, 310),
+                (BarcodeId::MixedAadc, 10)
+            ]
+        );
+        assert!((lnes[0].subtotal - 60.55).abs() < 1e-9);
+
+        // A missing price fails.
+        assert!(table
+            .postage_lines(&[tray(BarcodeId::CarrierRoute, 1)])
+            .is_err());
+    }
+
+    #[test]
+    fn test_builtin() {
+        let tables = RateTables::load("missing").unwrap();
+        let table = tables
+            .select(MailClass::Nonprofit, date(2024, 10, 1))
+            .unwrap();
+        assert_eq!(table.price(BarcodeId::FiveDigit).unwrap().1, 0.173);
+        assert_eq!(
+            table.price(BarcodeId::ThreeDigit).unwrap(),
+            (BarcodeId::Aadc, 0.197)
+        );
+        let table = tables
+            .select(MailClass::Marketing, date(2024, 10, 1))
+            .unwrap();
+        assert_eq!(table.price(BarcodeId::FiveDigit).unwrap().1, 0.331);
+        let table = tables
+            .select(MailClass::FirstClass, date(2024, 10, 1))
+            .unwrap();
+        assert_eq!(table.price(BarcodeId::FiveDigit).unwrap().1, 0.593);
+        assert!(tables
+            .select(MailClass::FirstClass, date(2024, 1, 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_load_dedup() {
+        let dir = std::env::temp_dir().join(format!("adr_rates_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // A copy of a built-in table, with a changed price.
+        let txt = RATES_BUILTIN[0].replace("0.173", "0.175");
+        fs::write(dir.join("marketing_nonprofit_2024-07-14.json"), txt).unwrap();
+
+        let tables = RateTables::load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(tables.0.len(), RATES_BUILTIN.len());
+        let table = tables
+            .select(MailClass::Nonprofit, date(2024, 10, 1))
+            .unwrap();
+        assert_eq!(table.price(BarcodeId::FiveDigit).unwrap().1, 0.175);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::core::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::source::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
 use scraper::{Html, Selector};
@@ -12,6 +13,9 @@ use std::path::Path;
 
 const FLE_PTH: &str = "senate.json";
 
+/// The source key.
+pub const KEY_SENATE: &str = "senate";
+
 /// The U.S. Senate consists of 100 members, with each of the 50 states represented by two senators regardless of population size.
 const CAP_PER: usize = 100;
 
@@ -31,44 +35,10 @@ impl Senate {
         }
     }
 
-    pub async fn load() -> Result<Senate> {
-        // Read file from disk.
-        let mut senate = match read_from_file::<Senate>(FLE_PTH) {
-            Ok(senate_from_disk) => senate_from_disk,
-            Err(_) => {
-                let mut senate = Senate::new();
-
-                // Fetch members.
-                let states = vec![
-                    "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "FL", "GA", "HI", "ID", "IL",
-                    "IN", "IA", "KS", "KY", "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT",
-                    "NE", "NV", "NH", "NJ", "NM", "NY", "NC", "ND", "OH", "OK", "OR", "PA", "RI",
-                    "SC", "SD", "TN", "TX", "UT", "VT", "VA", "WA", "WV", "WI", "WY",
-                ];
-                for state in states {
-                    let pers = senate.fetch_members(state).await?;
-                    senate.persons.extend(pers);
-                }
-
-                // Write file to disk.
-                write_to_file(&senate, FLE_PTH)?;
-
-                senate
-            }
-        };
-
-        println!("{} senators", senate.persons.len());
-
-        // Fetch addresses.
-        senate.fetch_adrs().await?;
-
-        Ok(senate)
-    }
-
     /// Fetch member from network.
-    pub async fn fetch_members(&self, state: &str) -> Result<Vec<Person>> {
+    pub async fn fetch_members_state(&self, state: &str) -> Result<Vec<Person>> {
         let url = format!("https://www.senate.gov/states/{state}/intro.htm");
-        let html = fetch_html(&url).await?;
+        let html = fetch_html(&url, KEY_SENATE).await?;
         let document = Html::parse_document(&html);
 
         let mut pers = Vec::new();
@@ -81,7 +51,7 @@ impl Senate {
                 let mut per = Person::default();
                 let full_name = elm_url.text().collect::<Vec<_>>().concat();
                 eprintln!("{}", full_name.trim());
-                per.name = name_clean(&full_name);
+                per.set_name(&full_name);
                 per.url = elm_url
                     .value()
                     .attr("href")
@@ -112,7 +82,8 @@ impl Senate {
         Ok(pers)
     }
 
-    pub async fn fetch_adrs(&mut self) -> Result<()> {
+    /// Fetch addresses for members without an address.
+    pub async fn fetch_member_adrs(&mut self) -> Result<()> {
         // Clone self for file writing.
         let mut self_clone = self.clone();
         let per_len = self.persons.len() as f64;
@@ -164,7 +135,7 @@ impl Senate {
 
             // Checkpoint save.
             // Write intermediate file to disk.
-            write_to_file(&self, FLE_PTH)?;
+            self.save()?;
         }
 
         Ok(())
@@ -229,10 +200,56 @@ impl Senate {
     }
 }
 
+impl Source for Senate {
+    fn key(&self) -> &'static str {
+        KEY_SENATE
+    }
+
+    fn file_path(&self) -> &'static str {
+        FLE_PTH
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn role(&self) -> Role {
+        self.role.clone()
+    }
+
+    fn persons(&self) -> &[Person] {
+        &self.persons
+    }
+
+    fn persons_mut(&mut self) -> &mut Vec<Person> {
+        &mut self.persons
+    }
+
+    fn fetch_members(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(async move {
+            let states = vec![
+                "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "FL", "GA", "HI", "ID", "IL", "IN",
+                "IA", "KS", "KY", "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT", "NE", "NV",
+                "NH", "NJ", "NM", "NY", "NC", "ND", "OH", "OK", "OR", "PA", "RI", "SC", "SD", "TN",
+                "TX", "UT", "VT", "VA", "WA", "WV", "WI", "WY",
+            ];
+            for state in states {
+                let pers = self.fetch_members_state(state).await?;
+                self.persons.extend(pers);
+            }
+            Ok(())
+        })
+    }
+
+    fn fetch_adrs(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(self.fetch_member_adrs())
+    }
+}
+
 /// Fetch and parse addresses and standardize with the USPS.
 pub async fn fetch_prs_std_adrs(per: &Person, url: &str) -> Result<Option<Vec<Address>>> {
     // Fetch html.
-    let html = fetch_html(url).await?;
+    let html = fetch_html(url, KEY_SENATE).await?;
 
     // Parse html to address lines.
     let adr_lnes_o = prs_adr_lnes(per, &html);
@@ -0,0 +1,173 @@
+use crate::core::*;
+use crate::executive::*;
+use crate::house::*;
+use crate::military::*;
+use crate::models::*;
+use crate::nasa::*;
+use crate::observer::*;
+use crate::prsr::*;
+use crate::senate::*;
+use crate::state::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+
+/// A boxed future returned by `Source` methods.
+///
+/// Boxing keeps `Source` usable as a trait object.
+pub type BoxFut<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + 'a>>;
+
+/// An organization roster of people and their addresses.
+///
+/// Implement `Source` and add it to `sources()` to include a new
+/// organization in the mailing.
+pub trait Source {
+    /// A short name used on the command line, such as `senate`.
+    fn key(&self) -> &'static str;
+
+    /// The roster file path.
+    fn file_path(&self) -> &'static str;
+
+    /// The organization name.
+    fn name(&self) -> &str;
+
+    fn role(&self) -> Role;
+
+    fn persons(&self) -> &[Person];
+
+    fn persons_mut(&mut self) -> &mut Vec<Person>;
+
+    /// Fetch members from network.
+    ///
+    /// A source may fetch addresses with its members.
+    fn fetch_members(&mut self) -> BoxFut<'_, ()>;
+
+    /// Fetch addresses for members without an address.
+    ///
+    /// Implementations checkpoint with `save()` after each member.
+    fn fetch_adrs(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Indicates whether the roster is edited by hand instead of fetched.
+    fn is_manual(&self) -> bool {
+        false
+    }
+
+    /// Read members from the roster file.
+    ///
+    /// Parses name parts for rosters written without them.
+    fn read(&mut self) -> Result<()> {
+        let mut roster = read_from_file::<Roster>(self.file_path())?;
+        for per in roster
+            .persons
+            .iter_mut()
+            .filter(|per| per.name_parts.is_empty())
+        {
+            per.name_parts = name_parse(&per.name);
+        }
+        *self.persons_mut() = roster.persons;
+        Ok(())
+    }
+
+    /// Write members to the roster file.
+    fn save(&self) -> Result<()> {
+        let roster = RosterRef {
+            name: self.name(),
+            role: self.role(),
+            persons: self.persons(),
+        };
+        write_to_file(&roster, self.file_path())
+    }
+}
+
+impl dyn Source + '_ {
+    /// Read the roster from disk or fetch members from network,
+    /// then fetch any missing addresses.
+    pub async fn load(&mut self) -> Result<()> {
+        // Read file from disk.
+        if let Err(err) = self.read() {
+            eprintln!("err: read file: {err}");
+            self.fetch().await?;
+        }
+
+        println!("{}: {} people", self.name(), self.persons().len());
+
+        // Fetch addresses.
+        self.fetch_adrs().await
+    }
+
+    /// Fetch members from network and write file to disk.
+    ///
+    /// Replaces any roster on disk.
+    pub async fn fetch(&mut self) -> Result<()> {
+        if self.is_manual() {
+            return Err(anyhow!("{} roster is edited by hand", self.key()));
+        }
+        self.persons_mut().clear();
+
+        // Fetch members.
+        self.fetch_members().await?;
+
+        // Write file to disk.
+        self.save()
+    }
+}
+
+/// Every roster source, in mailing order.
+pub fn sources() -> Vec<Box<dyn Source>> {
+    vec![
+        Box::new(Military::new()),
+        Box::new(Nasa::new()),
+        Box::new(Executive::new()),
+        Box::new(Senate::new()),
+        Box::new(House::new()),
+        Box::new(State::new()),
+        Box::new(Observer::new()),
+    ]
+}
+
+/// Finds a roster source by its key.
+pub fn source(key: &str) -> Result<Box<dyn Source>> {
+    sources()
+        .into_iter()
+        .find(|src| src.key() == key)
+        .ok_or(anyhow!("unknown source {key}"))
+}
+
+/// A roster file.
+#[derive(Debug, Deserialize)]
+struct Roster {
+    name: String,
+    role: Role,
+    persons: Vec<Person>,
+}
+
+/// A borrowed roster for writing files.
+#[derive(Debug, Serialize)]
+struct RosterRef<'a> {
+    name: &'a str,
+    role: Role,
+    persons: &'a [Person],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_keys_unique() {
+        let mut keys: Vec<&str> = sources().iter().map(|src| src.key()).collect();
+        let len = keys.len();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), len);
+    }
+
+    #[test]
+    fn test_source_find() {
+        assert_eq!(source("senate").unwrap().name(), "U.S. Senate");
+        assert!(source("bogus").is_err());
+    }
+}
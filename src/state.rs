@@ -1,6 +1,7 @@
 use crate::core::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::source::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
 use reqwest::Client;
@@ -13,6 +14,9 @@ use std::path::Path;
 
 const FLE_PTH: &str = "state.json";
 
+/// The source key.
+pub const KEY_STATE: &str = "state";
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct State {
     pub name: String,
@@ -30,38 +34,10 @@ impl State {
         }
     }
 
-    pub async fn load() -> Result<State> {
-        // Read file from disk.
-        let mut state = match read_from_file::<State>(FLE_PTH) {
-            Ok(state_from_disk) => state_from_disk,
-            Err(_) => {
-                let mut state = State::new();
-
-                // Fetch members.
-                for state_name in state_names() {
-                    let per = state.fetch_member(state_name).await?;
-                    state.persons.push(per);
-                }
-
-                // Write file to disk.
-                write_to_file(&state, FLE_PTH)?;
-
-                state
-            }
-        };
-
-        println!("{} governors", state.persons.len());
-
-        // Fetch addresses.
-        state.fetch_adrs().await?;
-
-        Ok(state)
-    }
-
     /// Fetch member from network.
     pub async fn fetch_member(&self, state_name: &str) -> Result<Person> {
         let url = format!("https://www.nga.org/governors/{state_name}/");
-        let html = fetch_html(&url).await?;
+        let html = fetch_html(&url, KEY_STATE).await?;
         let document = Html::parse_document(&html);
         let mut per = Person::default();
 
@@ -69,7 +45,7 @@ impl State {
         let name_sel = Selector::parse("h1.title").expect("Invalid selector");
         if let Some(elm) = document.select(&name_sel).next() {
             let full_name = elm.text().collect::<Vec<_>>().concat();
-            per.name = name_clean(&full_name);
+            per.set_name(&full_name);
             if per.name.is_empty() {
                 return Err(anyhow!("name is empty{:?}", per));
             }
@@ -95,7 +71,8 @@ impl State {
         Ok(per)
     }
 
-    pub async fn fetch_adrs(&mut self) -> Result<()> {
+    /// Fetch addresses for members without an address.
+    pub async fn fetch_member_adrs(&mut self) -> Result<()> {
         // Clone self for file writing.
         let mut self_clone = self.clone();
         let per_len = self.persons.len() as f64;
@@ -152,17 +129,57 @@ impl State {
 
             // Checkpoint save.
             // Write intermediate file to disk.
-            write_to_file(&self, FLE_PTH)?;
+            self.save()?;
         }
 
         Ok(())
     }
 }
 
+impl Source for State {
+    fn key(&self) -> &'static str {
+        KEY_STATE
+    }
+
+    fn file_path(&self) -> &'static str {
+        FLE_PTH
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn role(&self) -> Role {
+        self.role.clone()
+    }
+
+    fn persons(&self) -> &[Person] {
+        &self.persons
+    }
+
+    fn persons_mut(&mut self) -> &mut Vec<Person> {
+        &mut self.persons
+    }
+
+    fn fetch_members(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(async move {
+            for state_name in state_names() {
+                let per = self.fetch_member(state_name).await?;
+                self.persons.push(per);
+            }
+            Ok(())
+        })
+    }
+
+    fn fetch_adrs(&mut self) -> BoxFut<'_, ()> {
+        Box::pin(self.fetch_member_adrs())
+    }
+}
+
 /// Fetch and parse addresses and standardize with the USPS.
 pub async fn fetch_prs_std_adrs(state: &str, url: &str) -> Result<Option<Vec<Address>>> {
     // Fetch html.
-    let html = fetch_html(url).await?;
+    let html = fetch_html(url, KEY_STATE).await?;
 
     // Parse html to address lines.
     let adr_lnes_o = prs_adr_lnes(state, &html);
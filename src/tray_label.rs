@@ -0,0 +1,285 @@
+use crate::core::*;
+use crate::imtb::*;
+use crate::mailing::*;
+use crate::rates::*;
+use anyhow::{anyhow, Result};
+use printpdf::*;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+const LYR_LABELS: &str = "LABELS";
+
+// US Letter sheet.
+const WIDTH: Mm = Mm(215.9);
+const HEIGHT: Mm = Mm(279.4);
+
+// Sheet of 4 x 2 inch labels, 2 across and 5 down, such as Avery 5163.
+// USPS tray labels are about 3-1/4 x 2 inches; the extra width is margin.
+const LBL_COLS: usize = 2;
+const LBL_ROWS: usize = 5;
+const LBL_WIDTH: Mm = Mm(101.6);
+const LBL_HEIGHT: Mm = Mm(50.8);
+const LBL_GAP_X: Mm = Mm(4.8);
+const MARGIN_LEFT: Mm = Mm(4.0);
+const MARGIN_TOP: Mm = Mm(12.7);
+
+/// Text inset from the label edge.
+const LBL_PAD: Mm = Mm(8.0);
+
+// Tray barcode dimensions.
+// See "Intelligent Mail Tray Barcode" specification USPS-B-3001.
+const BAR_MODULE: Mm = Mm(0.33);
+const BAR_HEIGHT: Mm = Mm(12.7);
+
+/// USPS processing code for letter trays.
+pub const PROC_CODE_LTR: &str = "000";
+
+pub struct TrayLabelDocument {
+    pub name: String,
+    pub doc: PdfDocumentReference,
+    pub font: IndirectFontRef,
+    pub font_bold: IndirectFontRef,
+    /// Labels created so far.
+    pub lbl_cnt: usize,
+    pub pg_idx: PdfPageIndex,
+    pub lyr_idx: PdfLayerIndex,
+}
+
+impl TrayLabelDocument {
+    pub fn new(name: String) -> Self {
+        // Setup document.
+        let (doc, pg_idx, lyr_idx) = PdfDocument::new(&name, WIDTH, HEIGHT, LYR_LABELS);
+
+        // Setup fonts.
+        let font = doc.add_builtin_font(BuiltinFont::Helvetica).unwrap();
+        let font_bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).unwrap();
+
+        Self {
+            name,
+            doc,
+            font,
+            font_bold,
+            lbl_cnt: 0,
+            pg_idx,
+            lyr_idx,
+        }
+    }
+
+    /// Create one label per tray.
+    ///
+    /// Labels fill each sheet left to right, top to bottom.
+    pub fn create_label(&mut self, tray: &MailTray) -> Result<()> {
+        let digits = tray.imtb()?;
+        let widths = code128c_encode(&digits)?;
+
+        // Start a new sheet when the current sheet is full.
+        let lbl_idx = self.lbl_cnt % (LBL_COLS * LBL_ROWS);
+        if self.lbl_cnt != 0 && lbl_idx == 0 {
+            (self.pg_idx, self.lyr_idx) = self.doc.add_page(WIDTH, HEIGHT, LYR_LABELS);
+        }
+        self.lbl_cnt += 1;
+        let lyr = self.doc.get_page(self.pg_idx).get_layer(self.lyr_idx);
+
+        // Upper left corner of the label.
+        let col = (lbl_idx % LBL_COLS) as f32;
+        let row = (lbl_idx / LBL_COLS) as f32;
+        let x = MARGIN_LEFT + Mm((LBL_WIDTH.0 + LBL_GAP_X.0) * col) + LBL_PAD;
+        let y = HEIGHT - MARGIN_TOP - Mm(LBL_HEIGHT.0 * row);
+
+        // Write the tray name and size in the upper right corner.
+        // Matches the tray to its envelope files.
+        let size = match tray.size {
+            TraySize::OneFoot => "1FT",
+            TraySize::TwoFoot => "2FT",
+        };
+        lyr.use_text(
+            format!("TRAY {} {}", tray.name, size),
+            7.0,
+            x + LBL_WIDTH - LBL_PAD * 2.0 - Mm(12.0),
+            y - Mm(5.0),
+            &self.font,
+        );
+
+        // Draw barcode bars.
+        // Widths alternate bar and space, starting with a bar.
+        let bar_top = y - Mm(7.0);
+        let mut bar_x = x;
+        for (idx, width) in widths.iter().enumerate() {
+            let bar_width = BAR_MODULE * *width as f32;
+            if idx % 2 == 0 {
+                lyr.add_rect(Rect::new(
+                    bar_x,
+                    bar_top - BAR_HEIGHT,
+                    bar_x + bar_width,
+                    bar_top,
+                ));
+            }
+            bar_x += bar_width;
+        }
+
+        // Write human-readable barcode digits.
+        lyr.use_text(&digits, 8.0, x, bar_top - BAR_HEIGHT - Mm(3.5), &self.font);
+
+        // Write label lines.
+        // The barcode is above the destination line.
+        // See USPS tray label guidelines https://pe.usps.com/text/dmm300/708.htm#ep1159548.
+        lyr.use_text(dest_line(tray), 12.0, x, y - Mm(30.0), &self.font_bold);
+        lyr.use_text(
+            content_line(CFG.mail_class, tray.barcode_id),
+            12.0,
+            x,
+            y - Mm(36.0),
+            &self.font_bold,
+        );
+        lyr.use_text(origin_line(), 8.0, x, y - Mm(41.0), &self.font);
+
+        Ok(())
+    }
+
+    /// Writes the document to disk.
+    pub fn save<P: AsRef<Path>>(self, pth: P) -> Result<()> {
+        self.doc
+            .save(&mut BufWriter::new(File::create(pth.as_ref())?))?;
+        Ok(())
+    }
+}
+
+/// The destination line, such as `WASHINGTON DC 20510`.
+///
+/// Uses the tray destination from presort.
+pub fn dest_line(tray: &MailTray) -> String {
+    if tray.dest.name.is_empty() {
+        return tray.dest.zip.clone();
+    }
+    tray.dest.name.to_uppercase()
+}
+
+/// The content line for automation letters, such as `STD LTR 5D BC`.
+///
+/// See DMM 708.6.2.4 content line abbreviations.
+pub fn content_line(mail_class: MailClass, barcode_id: BarcodeId) -> String {
+    let lvl = match barcode_id {
+        BarcodeId::Default => "LTR",
+        BarcodeId::CarrierRoute => "LTR CR",
+        BarcodeId::FiveDigit => "LTR 5D BC",
+        BarcodeId::ThreeDigit => "LTR 3D BC",
+        BarcodeId::Aadc => "LTR AADC BC",
+        BarcodeId::MixedAadc => "LTR BC WKG",
+    };
+    format!("{} {}", mail_class.content_abbr(), lvl)
+}
+
+/// The content identifier number (CIN) for automation letter trays.
+///
+/// Pairs with the content line. Nonprofit and commercial Marketing Mail
+/// share CINs. Fails for levels without an automation letter tray.
+///
+/// From DMM 708 Exhibit 6.2.4, 3-digit content identifier numbers, which
+/// the USPS Labeling Lists repeat.
+/// See https://pe.usps.com/text/dmm300/708.htm.
+pub fn cin(mail_class: MailClass, barcode_id: BarcodeId) -> Result<&'static str> {
+    let is_fcm = mail_class == MailClass::FirstClass;
+    match (is_fcm, barcode_id) {
+        // FCM LTR 5D BC, 3D BC, AADC BC and BC WKG.
+        (true, BarcodeId::FiveDigit) => Ok("230"),
+        (true, BarcodeId::ThreeDigit) => Ok("231"),
+        (true, BarcodeId::Aadc) => Ok("232"),
+        (true, BarcodeId::MixedAadc) => Ok("233"),
+        // STD LTR 5D BC, 3D BC, AADC BC and BC WKG.
+        (false, BarcodeId::FiveDigit) => Ok("551"),
+        (false, BarcodeId::ThreeDigit) => Ok("552"),
+        (false, BarcodeId::Aadc) => Ok("553"),
+        (false, BarcodeId::MixedAadc) => Ok("554"),
+        (_, BarcodeId::Default | BarcodeId::CarrierRoute) => Err(anyhow!(
+            "no {} automation letter CIN for {:?} trays",
+            mail_class,
+            barcode_id
+        )),
+    }
+}
+
+/// The origin line with the mailer name, city and state.
+pub fn origin_line() -> String {
+    format!("{} {} {}", CFG.from.name, CFG.from.city, CFG.from.state).to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llist::*;
+
+    #[test]
+    fn test_dest_line() {
+        let tray = |zip: &str, name: &str| MailTray {
+            name: "A".into(),
+            size: TraySize::OneFoot,
+            barcode_id: BarcodeId::FiveDigit,
+            dest: Label {
+                zip: zip.into(),
+                name: name.into(),
+            },
+            serial: 0,
+            mailpieces: Vec::new(),
+        };
+        let cases = vec![
+            (tray("20510", "Washington DC 20510"), "WASHINGTON DC 20510"),
+            (
+                tray("200", "AADC WASHINGTON DC 200"),
+                "AADC WASHINGTON DC 200",
+            ),
+            (tray("20001", ""), "20001"),
+        ];
+
+        for (tray, expected) in cases {
+            assert_eq!(dest_line(&tray), expected);
+        }
+    }
+
+    #[test]
+    fn test_cin() {
+        let cases = vec![
+            (MailClass::FirstClass, BarcodeId::Default, None),
+            (MailClass::FirstClass, BarcodeId::CarrierRoute, None),
+            (MailClass::FirstClass, BarcodeId::FiveDigit, Some("230")),
+            (MailClass::FirstClass, BarcodeId::ThreeDigit, Some("231")),
+            (MailClass::FirstClass, BarcodeId::Aadc, Some("232")),
+            (MailClass::FirstClass, BarcodeId::MixedAadc, Some("233")),
+            (MailClass::Nonprofit, BarcodeId::Default, None),
+            (MailClass::Nonprofit, BarcodeId::CarrierRoute, None),
+            (MailClass::Nonprofit, BarcodeId::FiveDigit, Some("551")),
+            (MailClass::Nonprofit, BarcodeId::ThreeDigit, Some("552")),
+            (MailClass::Nonprofit, BarcodeId::Aadc, Some("553")),
+            (MailClass::Nonprofit, BarcodeId::MixedAadc, Some("554")),
+            (MailClass::Marketing, BarcodeId::FiveDigit, Some("551")),
+            (MailClass::Marketing, BarcodeId::ThreeDigit, Some("552")),
+            (MailClass::Marketing, BarcodeId::Aadc, Some("553")),
+            (MailClass::Marketing, BarcodeId::MixedAadc, Some("554")),
+        ];
+
+        for (mail_class, barcode_id, expected) in cases {
+            assert_eq!(
+                cin(mail_class, barcode_id).ok(),
+                expected,
+                "{mail_class} {barcode_id:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_content_line() {
+        let cases = vec![
+            (MailClass::Nonprofit, BarcodeId::FiveDigit, "STD LTR 5D BC"),
+            (MailClass::Marketing, BarcodeId::Aadc, "STD LTR AADC BC"),
+            (
+                MailClass::FirstClass,
+                BarcodeId::MixedAadc,
+                "FCM LTR BC WKG",
+            ),
+        ];
+
+        for (mail_class, barcode_id, expected) in cases {
+            assert_eq!(content_line(mail_class, barcode_id), expected);
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use crate::core::*;
+use crate::imb::*;
 use crate::models::*;
 use anyhow::{anyhow, Result};
 use reqwest::Client;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use StdAdr::*;
 
 pub async fn standardize_addresses(mut adrs: Vec<Address>) -> Result<Vec<Address>> {
@@ -51,6 +53,30 @@ pub async fn standardize_addresses(mut adrs: Vec<Address>) -> Result<Vec<Address
     Ok(adrs)
 }
 
+/// Standardizes the addresses of each person with the USPS.
+///
+/// Each distinct address is standardized once.
+pub async fn standardize_persons(pers: &mut [Person]) -> Result<()> {
+    let mut std_adrs: BTreeMap<Address, Address> = BTreeMap::new();
+    for per in pers.iter_mut() {
+        if let Some(adrs) = per.adrs.as_mut() {
+            for adr in adrs.iter_mut() {
+                if !std_adrs.contains_key(adr) {
+                    let std_adr = standardize_addresses(vec![adr.clone()]).await?.remove(0);
+                    std_adrs.insert(adr.clone(), std_adr);
+                }
+                *adr = std_adrs[adr].clone();
+            }
+
+            // Deduplicate standardized addresses.
+            adrs.sort_unstable();
+            adrs.dedup();
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(PartialEq)]
 pub enum StdAdr {
     AsIs,
@@ -180,7 +206,7 @@ fn from(adr: &mut Address, usps: USPSAddress) {
 /// Encodes mailing information to characters
 /// `F`,`A`,`D`,`T`
 /// for use with a barcode font.
-pub async fn encode_barcode(
+pub fn encode_barcode(
     barcode_id: &str,
     service_id: &str, // STID
     mailer_id: &str,
@@ -213,106 +239,163 @@ pub async fn encode_barcode(
     }
 
     // Encode information.
-    let qry = format!(
-        "{}{}{}{}{}",
-        barcode_id, service_id, mailer_id, serial_id, routing_code
-    );
-    // eprintln!("qry:{qry}");
-    let url = format!(
-        "https://postalpro.usps.com/ppro-tools-api/imb/encode?imb={}",
-        qry
-    );
-    eprintln!("url:{url}");
-
-    let res = CLI.get(&url).send().await?.json::<ImbResponse>().await?;
-
-    if res.code != "00" {
-        return Err(anyhow!("Error from API: {}", res.code));
+    let tracking_code = format!("{}{}{}{}", barcode_id, service_id, mailer_id, serial_id);
+    imb_encode(&tracking_code, routing_code)
+}
+
+/// Verifies that barcode characters
+/// `F`,`A`,`D`,`T`
+/// decode to the expected mailing information.
+pub fn verify_barcode(
+    barcode: &str,
+    barcode_id: &str,
+    service_id: &str, // STID
+    mailer_id: &str,
+    serial_id: &str,
+    routing_code: &str,
+) -> Result<()> {
+    let data = imb_decode(barcode)?;
+
+    // Compare the tracking code as a whole.
+    // The decoded mailer ID length depends on its first digit.
+    let tracking_code = format!("{}{}{}{}", barcode_id, service_id, mailer_id, serial_id);
+    if data.tracking_code() != tracking_code {
+        return Err(anyhow!(
+            "barcode tracking code {} doesn't match {}",
+            data.tracking_code(),
+            tracking_code
+        ));
+    }
+    if data.routing_code != routing_code {
+        return Err(anyhow!(
+            "barcode routing code {:?} doesn't match {:?}",
+            data.routing_code,
+            routing_code
+        ));
     }
 
-    // Return the encoding.
-    Ok(res.imb)
-}
-#[derive(Deserialize)]
-struct ImbResponse {
-    code: String,
-    imb: String,
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[tokio::test]
-    async fn test_valid_barcode() {
+    #[test]
+    fn test_valid_barcode() {
         let barcode_id = "50";
         let service_id = "301";
         let mailer_id = "899999999";
         let serial_id = "981000";
         let zip_code = "12345";
 
-        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code).await;
+        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code);
         assert!(result.is_ok());
-        assert!(!result.unwrap().is_empty());
+        assert_eq!(result.unwrap().len(), BAR_CNT);
+    }
+
+    #[test]
+    fn test_valid_barcode_golden() {
+        // Example from the "USPS-B-3200 Intelligent Mail Barcode 4-State" specification.
+        let barcode_id = "01";
+        let service_id = "234";
+        let mailer_id = "567094987";
+        let serial_id = "654321";
+        let zip_code = "01234567891";
+
+        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code);
+        assert_eq!(
+            result.unwrap(),
+            "AADTFFDFTDADTAADAATFDTDDAAADDTDTTDAFADADDDTFFFDDTTTADFAAADFTDAADA"
+        );
+    }
+
+    #[test]
+    fn test_verify_barcode() {
+        let barcode = encode_barcode("50", "272", "899999999", "981000", "20500000500").unwrap();
+
+        assert!(
+            verify_barcode(&barcode, "50", "272", "899999999", "981000", "20500000500").is_ok()
+        );
+        // Stale serial.
+        assert!(
+            verify_barcode(&barcode, "50", "272", "899999999", "981001", "20500000500").is_err()
+        );
+        // Stale routing code.
+        assert!(verify_barcode(&barcode, "50", "272", "899999999", "981000", "205000005").is_err());
+        // Different barcode id.
+        assert!(
+            verify_barcode(&barcode, "20", "272", "899999999", "981000", "20500000500").is_err()
+        );
+        // Corrupt barcode.
+        assert!(verify_barcode(
+            &barcode[1..],
+            "50",
+            "272",
+            "899999999",
+            "981000",
+            "20500000500"
+        )
+        .is_err());
     }
 
-    #[tokio::test]
-    async fn test_invalid_barcode_id() {
+    #[test]
+    fn test_invalid_barcode_id() {
         let barcode_id = "5a"; // Invalid
         let service_id = "301";
         let mailer_id = "899999999";
         let serial_id = "981000";
         let zip_code = "01926";
 
-        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code).await;
+        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code);
         assert!(result.is_err());
     }
 
-    #[tokio::test]
-    async fn test_invalid_service_id() {
+    #[test]
+    fn test_invalid_service_id() {
         let barcode_id = "50";
         let service_id = "30a"; // Invalid
         let mailer_id = "899999999";
         let serial_id = "981000";
         let zip_code = "01926";
 
-        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code).await;
+        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code);
         assert!(result.is_err());
     }
 
-    #[tokio::test]
-    async fn test_invalid_mailer_id() {
+    #[test]
+    fn test_invalid_mailer_id() {
         let barcode_id = "50";
         let service_id = "301";
         let mailer_id = "89999999a"; // Invalid
         let serial_id = "981000";
         let zip_code = "01926";
 
-        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code).await;
+        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code);
         assert!(result.is_err());
     }
 
-    #[tokio::test]
-    async fn test_invalid_serial_id() {
+    #[test]
+    fn test_invalid_serial_id() {
         let barcode_id = "50";
         let service_id = "301";
         let mailer_id = "899999999";
         let serial_id = "98100a"; // Invalid
         let zip_code = "01926";
 
-        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code).await;
+        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code);
         assert!(result.is_err());
     }
 
-    #[tokio::test]
-    async fn test_invalid_zip_code() {
+    #[test]
+    fn test_invalid_zip_code() {
         let barcode_id = "50";
         let service_id = "301";
         let mailer_id = "899999999";
         let serial_id = "981000";
         let zip_code = "0192a"; // Invalid
 
-        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code).await;
+        let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code);
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,278 @@
+use crate::envelope::*;
+use crate::layout::*;
+use crate::models::*;
+use anyhow::{anyhow, Result};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use std::path::Path;
+
+// US Letter sheet.
+const LTR_WIDTH: f32 = 215.9;
+const LTR_HEIGHT: f32 = 279.4;
+
+/// The top panel of a letter folded in thirds, which faces the windows.
+const LTR_PANEL: f32 = LTR_HEIGHT / 3.0;
+
+/// The letter font resource name.
+const FNT_NME: &str = "FAdr";
+
+// Intelligent Mail barcode bars, in millimeters.
+// See USPS-B-3200 Intelligent Mail barcode specification.
+const BAR_PITCH: f32 = 25.4 / 22.0;
+const BAR_WIDTH: f32 = 0.508;
+const BAR_FULL: f32 = 3.683;
+const BAR_TRACKER: f32 = 1.219;
+const BAR_CNT: usize = 65;
+
+/// The address blocks printed on the first page of a letter for a
+/// double-window envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowBlock {
+    pub from_lnes: Vec<String>,
+    pub adr_lnes: Vec<String>,
+    /// The barcode bars, `F`, `A`, `D` and `T`.
+    pub barcode: String,
+    pub lyt: Layout,
+}
+
+impl WindowBlock {
+    /// Places the address blocks for a mailpiece in the windows.
+    ///
+    /// Fails when a block does not fit its window.
+    pub fn new(windows: &Windows, from_lnes: Vec<String>, to: &Mailpiece) -> Result<WindowBlock> {
+        if to.barcode.len() != BAR_CNT || !to.barcode.chars().all(|c| "FADT".contains(c)) {
+            return Err(anyhow!("missing barcode for {}", to));
+        }
+        let adr_lnes = adr_lines(to);
+        let lyt = Layout::new(
+            EnvelopeSpec::No10Window,
+            windows,
+            &from_lnes,
+            &adr_lnes,
+            0,
+            None,
+        )
+        .map_err(|err| anyhow!("unable to lay out letter for {}: {}", to, err))?;
+        Ok(WindowBlock {
+            from_lnes,
+            adr_lnes,
+            barcode: to.barcode.clone(),
+            lyt,
+        })
+    }
+
+    /// Letter page operations that draw the blocks.
+    pub fn operations(&self) -> Vec<Operation> {
+        let mut ret = Vec::new();
+
+        // Write the return address and address lines.
+        let blks = [
+            (
+                &self.from_lnes,
+                self.lyt.from,
+                self.lyt.from_font_size,
+                self.lyt.from_lne_height,
+            ),
+            (
+                &self.adr_lnes,
+                self.lyt.adr,
+                self.lyt.adr_font_size,
+                self.lyt.adr_lne_height,
+            ),
+        ];
+        for (lnes, pos, font_size, lne_height) in blks {
+            let (x, y) = ltr_pt(&self.lyt, pos);
+            ret.push(Operation::new("BT", vec![]));
+            ret.push(Operation::new("Tf", vec![FNT_NME.into(), font_size.into()]));
+            ret.push(Operation::new("TL", vec![lne_height.into()]));
+            ret.push(Operation::new("Td", vec![x.into(), y.into()]));
+            for lne in lnes.iter() {
+                ret.push(Operation::new(
+                    "Tj",
+                    vec![Object::String(win_ansi(lne), StringFormat::Literal)],
+                ));
+                ret.push(Operation::new("T*", vec![]));
+            }
+            ret.push(Operation::new("ET", vec![]));
+        }
+
+        // Draw barcode bars.
+        // Trackers are centered; ascenders and descenders extend from them.
+        let half = (BAR_FULL + BAR_TRACKER) / 2.0;
+        for (idx, bar) in self.barcode.chars().enumerate() {
+            let (lly, height) = match bar {
+                'F' => (0.0, BAR_FULL),
+                'A' => (BAR_FULL - half, half),
+                'D' => (0.0, half),
+                _ => ((BAR_FULL - BAR_TRACKER) / 2.0, BAR_TRACKER),
+            };
+            let pos = (
+                self.lyt.barcode.llx + idx as f32 * BAR_PITCH,
+                self.lyt.barcode.lly + lly,
+            );
+            let (x, y) = ltr_pt(&self.lyt, pos);
+            ret.push(Operation::new(
+                "re",
+                vec![
+                    x.into(),
+                    y.into(),
+                    (BAR_WIDTH / MM_PER_PT).into(),
+                    (height / MM_PER_PT).into(),
+                ],
+            ));
+        }
+        ret.push(Operation::new("f", vec![]));
+
+        ret
+    }
+}
+
+/// Converts an envelope position in millimeters to a letter page position
+/// in points.
+///
+/// The folded letter rests centered on the bottom of the envelope, with its
+/// top panel facing the windows.
+fn ltr_pt(lyt: &Layout, (x, y): (f32, f32)) -> (f32, f32) {
+    let x = x - (lyt.width - LTR_WIDTH) / 2.0;
+    let y = y + LTR_HEIGHT - LTR_PANEL;
+    (x / MM_PER_PT, y / MM_PER_PT)
+}
+
+/// The letter top margin in inches that keeps letter text below the
+/// windows.
+pub fn min_top_margin(windows: &Windows) -> f32 {
+    (LTR_PANEL - windows.from.lly.min(windows.to.lly)) / 25.4
+}
+
+/// Encodes text as WinAnsi, replacing other characters with `?`.
+fn win_ansi(txt: &str) -> Vec<u8> {
+    txt.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7E | 0xA0..=0xFF => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Writes the address blocks on the first page of each letter in a PDF.
+///
+/// Each block is paired with its letter's first page number, starting at 1.
+pub fn add_window_blocks<P: AsRef<Path>>(pth: P, blks: &[(usize, WindowBlock)]) -> Result<()> {
+    let pth = pth.as_ref();
+    let mut doc = Document::load(pth)?;
+    let pages = doc.get_pages();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    });
+
+    for (pg_num, blk) in blks.iter() {
+        let pg_num = *pg_num as u32;
+        let page_id =
+            *pages
+                .get(&pg_num)
+                .ok_or(anyhow!("missing letter page {} in {:?}", pg_num, pth))?;
+        add_page_font(&mut doc, page_id, font_id)?;
+
+        // Save and restore the graphics state around the existing content
+        // so the blocks draw in default page space.
+        let mut contents = doc.get_page_contents(page_id);
+        let q_id = doc.add_object(Stream::new(Dictionary::new(), b"q".to_vec()));
+        let mut ops = vec![Operation::new("Q", vec![])];
+        ops.extend(blk.operations());
+        let content = Content { operations: ops };
+        let blk_id = doc.add_object(Stream::new(Dictionary::new(), content.encode()?));
+        contents.insert(0, q_id);
+        contents.push(blk_id);
+        let page = doc.get_object_mut(page_id)?.as_dict_mut()?;
+        page.set(
+            "Contents",
+            contents
+                .into_iter()
+                .map(Object::Reference)
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    doc.save(pth)?;
+    Ok(())
+}
+
+/// Adds the address font to a page's resources.
+fn add_page_font(doc: &mut Document, page_id: ObjectId, font_id: ObjectId) -> Result<()> {
+    let resources = doc.get_or_create_resources(page_id)?.as_dict_mut()?;
+    if !resources.has(b"Font") {
+        resources.set("Font", Dictionary::new());
+    }
+    let fonts_id = match resources.get(b"Font")? {
+        Object::Reference(id) => Some(*id),
+        _ => None,
+    };
+    let fonts = match fonts_id {
+        Some(id) => doc.get_object_mut(id)?.as_dict_mut()?,
+        None => doc
+            .get_or_create_resources(page_id)?
+            .as_dict_mut()?
+            .get_mut(b"Font")?
+            .as_dict_mut()?,
+    };
+    fonts.set(FNT_NME, Object::Reference(font_id));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    #[test]
+    fn test_window_block() {
+        let barcode = "ATTFATTDTTADTAATTDTDTATTDAFDDFADFDFTFFFFFTATFAAAATDFFTDAADFTFDTDT";
+        let mut to = mailpiece("Jane Doe");
+        to.barcode = barcode.into();
+        let blk = WindowBlock::new(&Windows::default(), from_lnes(), &to).unwrap();
+        let ops = blk.operations();
+        assert_eq!(ops.iter().filter(|o| o.operator == "re").count(), BAR_CNT);
+        assert_eq!(ops.iter().filter(|o| o.operator == "Tj").count(), 3 + 3);
+
+        // Bars sit in the top panel of the letter, inside the page.
+        let (x, y) = ltr_pt(&blk.lyt, (blk.lyt.barcode.llx, blk.lyt.barcode.lly));
+        assert!(x > 0.0 && x < LTR_WIDTH / MM_PER_PT);
+        assert!(y > (LTR_HEIGHT - LTR_PANEL) / MM_PER_PT && y < LTR_HEIGHT / MM_PER_PT);
+
+        // A mailpiece without a barcode fails.
+        let bad = barcode.replacen('A', "X", 1);
+        let cases = vec!["", "FADT", bad.as_str()];
+        for barcode in cases {
+            to.barcode = barcode.into();
+            assert!(
+                WindowBlock::new(&Windows::default(), from_lnes(), &to).is_err(),
+                "{barcode}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_min_top_margin() {
+        // The recipient window bottom is 5/8 inch above the envelope bottom.
+        let margin = min_top_margin(&Windows::default());
+        assert!((margin - (LTR_PANEL - 15.9) / 25.4).abs() < 1e-4);
+        assert!(margin > 3.0);
+    }
+
+    #[test]
+    fn test_win_ansi() {
+        let cases = vec![
+            ("Dear Jane", "Dear Jane"),
+            ("José", "Jos\u{e9}"),
+            ("A→B", "A?B"),
+        ];
+
+        for (txt, expected) in cases {
+            let expected: Vec<u8> = expected.chars().map(|c| c as u8).collect();
+            assert_eq!(win_ansi(txt), expected);
+        }
+    }
+}